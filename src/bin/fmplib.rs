@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use burnfmlib::{FmpFile, ScriptTreeItem};
+
+/// Inspect FileMaker .fmp12 files from the command line.
+#[derive(Parser)]
+#[command(name = "fmplib", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Dump the whole decompiled file.
+    Dump {
+        file: PathBuf,
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+    },
+    /// List tables and their fields.
+    Tables { file: PathBuf },
+    /// List scripts in Script Workspace order, indented by folder.
+    Scripts {
+        file: PathBuf,
+        /// Print each script's steps as Script Workspace text.
+        #[arg(long)]
+        text: bool,
+    },
+    /// List layouts.
+    Layouts { file: PathBuf },
+    /// Print the relationship graph.
+    Graph {
+        file: PathBuf,
+        #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    Yaml,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+fn sorted<T>(map: &HashMap<usize, T>) -> Vec<(&usize, &T)> {
+    let mut entries = map.iter().collect::<Vec<_>>();
+    entries.sort_by_key(|(id, _)| **id);
+    entries
+}
+
+/* Decompile `path`, warning about anything that was skipped on the way. */
+fn open(path: &PathBuf) -> Result<FmpFile, String> {
+    let file = burnfmlib::open(path).map_err(|e| e.to_string())?;
+    for diagnostic in &file.diagnostics {
+        eprintln!("fmplib: warning: {}", diagnostic);
+    }
+    Ok(file)
+}
+
+fn dump(file: &FmpFile, format: Format) -> Result<String, String> {
+    match format {
+        Format::Json => serde_json::to_string_pretty(file).map_err(|e| e.to_string()),
+        Format::Yaml => serde_yaml_ng::to_string(file).map_err(|e| e.to_string()),
+    }
+}
+
+fn print_scripts(file: &FmpFile, items: &[ScriptTreeItem], depth: usize, text: bool) {
+    let indent = "\t".repeat(depth);
+    for item in items {
+        match item {
+            ScriptTreeItem::Script { id, name } => {
+                println!("{}{}\t{}", indent, id, name);
+                if let Some(script) = file.scripts.get(id).filter(|_| text) {
+                    for line in burnfmlib::script_to_text(script).lines() {
+                        println!("{}\t{}", indent, line);
+                    }
+                }
+            },
+            ScriptTreeItem::Folder { name, children, .. } => {
+                println!("{}{}/", indent, name);
+                print_scripts(file, children, depth + 1, text);
+            },
+            ScriptTreeItem::Separator { .. } => println!("{}-", indent),
+        }
+    }
+}
+
+fn run(command: Command) -> Result<(), String> {
+    match command {
+        Command::Dump { file, format } => {
+            let file = open(&file)?;
+            println!("{}", dump(&file, format)?);
+        },
+        Command::Tables { file } => {
+            let file = open(&file)?;
+            for (id, table) in sorted(&file.tables) {
+                println!("{}\t{}", id, table.table_name);
+                let mut fields = table.fields.iter().collect::<Vec<_>>();
+                fields.sort_by_key(|(id, _)| **id);
+                for (id, field) in fields {
+                    println!("\t{}\t{}\t{:?}", id, field.field_name, field.data_type);
+                }
+            }
+        },
+        Command::Scripts { file, text } => {
+            let file = open(&file)?;
+            print_scripts(&file, &file.script_tree, 0, text);
+        },
+        Command::Layouts { file } => {
+            let file = open(&file)?;
+            for (id, layout) in sorted(&file.layouts) {
+                println!("{}\t{}", id, layout.layout_name);
+            }
+        },
+        Command::Graph { file, format } => {
+            let graph = open(&file)?.relationship_graph();
+            match format {
+                GraphFormat::Dot => print!("{}", graph.to_dot()),
+                GraphFormat::Mermaid => print!("{}", graph.to_mermaid()),
+            }
+        },
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse().command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("fmplib: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
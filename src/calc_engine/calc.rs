@@ -0,0 +1,318 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+use crate::calc_engine::functions::get_function;
+use crate::calc_engine::number::Decimal;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+    Concatenate,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    And,
+    Or,
+    Xor,
+}
+
+impl BinaryOp {
+    /// Binding strength, following FileMaker's order of operations.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            BinaryOp::Or | BinaryOp::Xor => 1,
+            BinaryOp::And => 2,
+            BinaryOp::Equal | BinaryOp::NotEqual
+                | BinaryOp::Less | BinaryOp::LessEqual
+                | BinaryOp::Greater | BinaryOp::GreaterEqual => 4,
+            BinaryOp::Concatenate => 5,
+            BinaryOp::Add | BinaryOp::Subtract => 6,
+            BinaryOp::Multiply | BinaryOp::Divide => 7,
+            BinaryOp::Power => 8,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Subtract => "-",
+            BinaryOp::Multiply => "*",
+            BinaryOp::Divide => "/",
+            BinaryOp::Power => "^",
+            BinaryOp::Concatenate => "&",
+            BinaryOp::Equal => "=",
+            BinaryOp::NotEqual => "≠",
+            BinaryOp::Less => "<",
+            BinaryOp::LessEqual => "≤",
+            BinaryOp::Greater => ">",
+            BinaryOp::GreaterEqual => "≥",
+            BinaryOp::And => "and",
+            BinaryOp::Or => "or",
+            BinaryOp::Xor => "xor",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UnaryOp {
+    Negate,
+    Not,
+}
+
+impl UnaryOp {
+    pub fn precedence(&self) -> u8 {
+        match self {
+            UnaryOp::Not => 3,
+            UnaryOp::Negate => 9,
+        }
+    }
+}
+
+/// A field as referenced from a calculation, by table occurrence and field id.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldRef {
+    pub table_occurrence: usize,
+    pub field: usize,
+    /// `Table Occurrence::Field`, once resolved against the file's schema.
+    pub name: Option<String>,
+}
+
+impl fmt::Display for FieldRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "<unresolved {}:{}>", self.table_occurrence, self.field),
+        }
+    }
+}
+
+/// A decoded FileMaker calculation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Calc {
+    Number(Decimal),
+    Text(String),
+    /// `$local`, `$$global` or a name bound by `Let`.
+    Variable(String),
+    Field(FieldRef),
+    /// Built-in function call, identified by its bytecode id.
+    Call { function: u16, args: Vec<Calc> },
+    /// Custom function call, identified by the custom function's id in its file.
+    CustomCall { function: usize, name: Option<String>, args: Vec<Calc> },
+    Let { bindings: Vec<(String, Calc)>, body: Box<Calc> },
+    Case { arms: Vec<(Calc, Calc)>, default: Option<Box<Calc>> },
+    If { condition: Box<Calc>, then: Box<Calc>, otherwise: Option<Box<Calc>> },
+    Unary { op: UnaryOp, operand: Box<Calc> },
+    Binary { op: BinaryOp, left: Box<Calc>, right: Box<Calc> },
+    /// Parentheses written by the calculation's author.
+    Group(Box<Calc>),
+    /// `[ a ; b ]`, as passed to functions such as `Substitute`.
+    List(Vec<Calc>),
+}
+
+impl Calc {
+    fn children(&self) -> Vec<&Calc> {
+        match self {
+            Calc::Number(_) | Calc::Text(_) | Calc::Variable(_) | Calc::Field(_) => vec![],
+            Calc::Call { args, .. } | Calc::CustomCall { args, .. } | Calc::List(args) => args.iter().collect(),
+            Calc::Let { bindings, body } => {
+                bindings.iter().map(|(_, value)| value).chain([body.as_ref()]).collect()
+            },
+            Calc::Case { arms, default } => {
+                arms.iter().flat_map(|(test, result)| [test, result]).chain(default.as_deref()).collect()
+            },
+            Calc::If { condition, then, otherwise } => {
+                [condition.as_ref(), then.as_ref()].into_iter().chain(otherwise.as_deref()).collect()
+            },
+            Calc::Unary { operand, .. } | Calc::Group(operand) => vec![operand],
+            Calc::Binary { left, right, .. } => vec![left, right],
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut Calc> {
+        match self {
+            Calc::Number(_) | Calc::Text(_) | Calc::Variable(_) | Calc::Field(_) => vec![],
+            Calc::Call { args, .. } | Calc::CustomCall { args, .. } | Calc::List(args) => args.iter_mut().collect(),
+            Calc::Let { bindings, body } => {
+                bindings.iter_mut().map(|(_, value)| value).chain([body.as_mut()]).collect()
+            },
+            Calc::Case { arms, default } => {
+                arms.iter_mut().flat_map(|(test, result)| [test, result]).chain(default.as_deref_mut()).collect()
+            },
+            Calc::If { condition, then, otherwise } => {
+                [condition.as_mut(), then.as_mut()].into_iter().chain(otherwise.as_deref_mut()).collect()
+            },
+            Calc::Unary { operand, .. } | Calc::Group(operand) => vec![operand],
+            Calc::Binary { left, right, .. } => vec![left, right],
+        }
+    }
+
+    /// Visit this expression and every expression nested in it, parents first.
+    pub fn walk(&self, visit: &mut dyn FnMut(&Calc)) {
+        visit(self);
+        for child in self.children() {
+            child.walk(visit);
+        }
+    }
+
+    /// Like [`Calc::walk`], allowing each expression to be modified in place.
+    pub fn walk_mut(&mut self, visit: &mut dyn FnMut(&mut Calc)) {
+        visit(self);
+        for child in self.children_mut() {
+            child.walk_mut(visit);
+        }
+    }
+
+    /// Name every field reference using `resolve`, leaving unknown ones unresolved.
+    pub fn resolve_fields(&mut self, resolve: &dyn Fn(&FieldRef) -> Option<String>) {
+        self.walk_mut(&mut |calc| {
+            if let Calc::Field(field) = calc {
+                field.name = resolve(field);
+            }
+        });
+    }
+
+    /// Name every custom function call using `resolve`, leaving unknown ones unresolved.
+    pub fn resolve_custom_functions(&mut self, resolve: &dyn Fn(usize) -> Option<String>) {
+        self.walk_mut(&mut |calc| {
+            if let Calc::CustomCall { function, name, .. } = calc {
+                *name = resolve(*function);
+            }
+        });
+    }
+
+    /// Ids of the custom functions called, in order of appearance.
+    pub fn custom_functions(&self) -> Vec<usize> {
+        let mut called = Vec::new();
+        self.walk(&mut |calc| {
+            if let Calc::CustomCall { function, .. } = calc {
+                if !called.contains(function) {
+                    called.push(*function);
+                }
+            }
+        });
+        called
+    }
+
+    /// Ids of called functions missing from the function table, in order of appearance.
+    pub fn unknown_functions(&self) -> Vec<u16> {
+        let mut unknown = Vec::new();
+        self.walk(&mut |calc| {
+            if let Calc::Call { function, .. } = calc {
+                if get_function(*function).is_none() && !unknown.contains(function) {
+                    unknown.push(*function);
+                }
+            }
+        });
+        unknown
+    }
+
+    /// Known functions called with a number of arguments they do not take, with the number found.
+    pub fn argument_count_mismatches(&self) -> Vec<(&'static str, usize)> {
+        let mut mismatches = Vec::new();
+        self.walk(&mut |calc| {
+            if let Calc::Call { function, args } = calc {
+                if let Some(f) = get_function(*function).filter(|f| !f.accepts(args.len())) {
+                    mismatches.push((f.name, args.len()));
+                }
+            }
+        });
+        mismatches
+    }
+}
+
+fn write_args(f: &mut fmt::Formatter<'_>, name: &str, args: &[&Calc]) -> fmt::Result {
+    write!(f, "{} ( ", name)?;
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            write!(f, " ; ")?;
+        }
+        write!(f, "{}", arg)?;
+    }
+    write!(f, " )")
+}
+
+impl fmt::Display for Calc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Calc::Number(n) => write!(f, "{}", n),
+            Calc::Text(s) => {
+                write!(f, "\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' => write!(f, "\\\"")?,
+                        '\\' => write!(f, "\\\\")?,
+                        '¶' => write!(f, "\\¶")?,
+                        '\r' => write!(f, "¶")?,
+                        c => write!(f, "{}", c)?,
+                    }
+                }
+                write!(f, "\"")
+            },
+            Calc::Variable(name) => write!(f, "{}", name),
+            Calc::Field(field) => write!(f, "{}", field),
+            Calc::Call { function, args } => {
+                let name = match get_function(*function) {
+                    Some(known) => known.name.to_string(),
+                    None => format!("<unknown function {}>", function),
+                };
+                if args.is_empty() {
+                    write!(f, "{}", name)
+                } else {
+                    write_args(f, &name, &args.iter().collect::<Vec<_>>())
+                }
+            },
+            Calc::CustomCall { function, name, args } => {
+                let name = match name {
+                    Some(name) => name.clone(),
+                    None => format!("<custom function {}>", function),
+                };
+                if args.is_empty() {
+                    write!(f, "{}", name)
+                } else {
+                    write_args(f, &name, &args.iter().collect::<Vec<_>>())
+                }
+            },
+            Calc::Let { bindings, body } => {
+                write!(f, "Let ( [ ")?;
+                for (i, (name, value)) in bindings.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ; ")?;
+                    }
+                    write!(f, "{} = {}", name, value)?;
+                }
+                write!(f, " ] ; {} )", body)
+            },
+            Calc::Case { arms, default } => {
+                let mut args = arms.iter().flat_map(|(test, result)| [test, result]).collect::<Vec<_>>();
+                args.extend(default.as_deref());
+                write_args(f, "Case", &args)
+            },
+            Calc::If { condition, then, otherwise } => {
+                let mut args = vec![condition.as_ref(), then.as_ref()];
+                args.extend(otherwise.as_deref());
+                write_args(f, "If", &args)
+            },
+            Calc::Unary { op: UnaryOp::Negate, operand } => write!(f, "-{}", operand),
+            Calc::Unary { op: UnaryOp::Not, operand } => write!(f, "not {}", operand),
+            Calc::Binary { op, left, right } => write!(f, "{} {} {}", left, op.symbol(), right),
+            Calc::Group(inner) => write!(f, "({})", inner),
+            Calc::List(items) => {
+                write!(f, "[ ")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ; ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, " ]")
+            },
+        }
+    }
+}
@@ -0,0 +1,382 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+use crate::calc_engine::calc::{BinaryOp, Calc, FieldRef, UnaryOp};
+use crate::calc_engine::functions::{FN_CASE, FN_IF, FN_LET};
+use crate::calc_engine::number::{decode_number, Decimal};
+use crate::util::format_decode::{fm_string_decrypt, get_int};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CalcError {
+    /// The bytecode ends in the middle of a token.
+    Truncated { offset: usize },
+    /// A byte that does not start any known token.
+    UnknownToken { opcode: u8, offset: usize },
+    /// A token that cannot appear at this point of the expression.
+    UnexpectedToken { offset: usize },
+    /// The bytecode ends before the expression is complete.
+    UnexpectedEnd,
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalcError::Truncated { offset } => write!(f, "calculation truncated at offset {}", offset),
+            CalcError::UnknownToken { opcode, offset } => {
+                write!(f, "unknown calculation token {:#04x} at offset {}", opcode, offset)
+            },
+            CalcError::UnexpectedToken { offset } => write!(f, "unexpected calculation token at offset {}", offset),
+            CalcError::UnexpectedEnd => write!(f, "calculation ends unexpectedly"),
+        }
+    }
+}
+
+impl std::error::Error for CalcError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(Decimal),
+    Text(String),
+    Name(String),
+    Field(FieldRef),
+    Function(u16),
+    CustomFunction(usize),
+    Open,
+    Close,
+    BracketOpen,
+    BracketClose,
+    Separator,
+    Not,
+    Op(BinaryOp),
+}
+
+fn take(bytecode: &[u8], offset: usize, len: usize) -> Result<&[u8], CalcError> {
+    bytecode.get(offset..offset + len).ok_or(CalcError::Truncated { offset })
+}
+
+/* Strings and names are a length byte followed by XOR encoded text. */
+fn take_string(bytecode: &[u8], offset: &mut usize) -> Result<String, CalcError> {
+    let len = take(bytecode, *offset, 1)?[0] as usize;
+    let s = fm_string_decrypt(take(bytecode, *offset + 1, len)?);
+    *offset += 1 + len;
+    Ok(s)
+}
+
+fn tokenize(bytecode: &[u8]) -> Result<Vec<(usize, Token)>, CalcError> {
+    let mut tokens = Vec::new();
+    let mut offset = 0;
+    while offset < bytecode.len() {
+        let start = offset;
+        let opcode = bytecode[offset];
+        offset += 1;
+        let token = match opcode {
+            0x03 => Token::Separator,
+            0x04 => Token::Open,
+            0x05 => Token::Close,
+            0x06 => Token::BracketOpen,
+            0x07 => Token::BracketClose,
+            /* Whitespace written by the author */
+            0x0C..=0x0E => continue,
+            0x10 => {
+                let number = take(bytecode, offset, 19)?.try_into().map_err(|_| CalcError::Truncated { offset })?;
+                offset += 19;
+                Token::Number(decode_number(number))
+            },
+            0x13 => Token::Text(take_string(bytecode, &mut offset)?),
+            0x15 => {
+                let id = get_int(take(bytecode, offset, 2)?) as u16;
+                offset += 2;
+                Token::Function(id)
+            },
+            0x17 => {
+                let id = get_int(take(bytecode, offset, 2)?);
+                offset += 2;
+                Token::CustomFunction(id)
+            },
+            0x16 => {
+                let ids = take(bytecode, offset, 4)?;
+                offset += 4;
+                Token::Field(FieldRef {
+                    table_occurrence: get_int(&ids[0..2]),
+                    field: get_int(&ids[2..4]),
+                    name: None,
+                })
+            },
+            0x1a => Token::Name(take_string(bytecode, &mut offset)?),
+            0x25 => Token::Op(BinaryOp::Add),
+            0x26 => Token::Op(BinaryOp::Subtract),
+            0x27 => Token::Op(BinaryOp::Multiply),
+            0x28 => Token::Op(BinaryOp::Divide),
+            0x29 => Token::Op(BinaryOp::Power),
+            0x41 => Token::Op(BinaryOp::Less),
+            0x43 => Token::Op(BinaryOp::LessEqual),
+            0x44 => Token::Op(BinaryOp::Equal),
+            0x46 => Token::Op(BinaryOp::NotEqual),
+            0x47 => Token::Op(BinaryOp::GreaterEqual),
+            0x49 => Token::Op(BinaryOp::Greater),
+            0x50 => Token::Op(BinaryOp::Concatenate),
+            0x51 => Token::Op(BinaryOp::And),
+            0x52 => Token::Op(BinaryOp::Or),
+            0x53 => Token::Op(BinaryOp::Xor),
+            0x54 => Token::Not,
+            _ => return Err(CalcError::UnknownToken { opcode, offset: start }),
+        };
+        tokens.push((start, token));
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn next(&mut self) -> Result<(usize, Token), CalcError> {
+        let token = self.tokens.get(self.pos).cloned().ok_or(CalcError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), CalcError> {
+        match self.next()? {
+            (_, t) if t == expected => Ok(()),
+            (offset, _) => Err(CalcError::UnexpectedToken { offset }),
+        }
+    }
+
+    fn expression(&mut self, min_precedence: u8) -> Result<Calc, CalcError> {
+        let mut left = self.prefix()?;
+        while let Some(Token::Op(op)) = self.peek() {
+            let op = *op;
+            if op.precedence() < min_precedence {
+                break;
+            }
+            self.pos += 1;
+            let right = self.expression(op.precedence() + 1)?;
+            left = Calc::Binary { op, left: Box::new(left), right: Box::new(right) };
+        }
+        Ok(left)
+    }
+
+    fn prefix(&mut self) -> Result<Calc, CalcError> {
+        let (offset, token) = self.next()?;
+        match token {
+            Token::Number(n) => Ok(Calc::Number(n)),
+            Token::Text(s) => Ok(Calc::Text(s)),
+            Token::Name(name) => Ok(Calc::Variable(name)),
+            Token::Field(field) => Ok(Calc::Field(field)),
+            Token::Op(BinaryOp::Subtract) => {
+                let operand = self.expression(UnaryOp::Negate.precedence())?;
+                Ok(Calc::Unary { op: UnaryOp::Negate, operand: Box::new(operand) })
+            },
+            Token::Not => {
+                let operand = self.expression(UnaryOp::Not.precedence())?;
+                Ok(Calc::Unary { op: UnaryOp::Not, operand: Box::new(operand) })
+            },
+            Token::Open => {
+                let inner = self.expression(0)?;
+                self.expect(Token::Close)?;
+                Ok(Calc::Group(Box::new(inner)))
+            },
+            Token::BracketOpen => {
+                let mut items = vec![self.expression(0)?];
+                loop {
+                    match self.next()? {
+                        (_, Token::Separator) => items.push(self.expression(0)?),
+                        (_, Token::BracketClose) => return Ok(Calc::List(items)),
+                        (offset, _) => return Err(CalcError::UnexpectedToken { offset }),
+                    }
+                }
+            },
+            Token::Function(FN_LET) => self.let_expression(),
+            Token::Function(id) => self.call(id, offset),
+            Token::CustomFunction(function) => {
+                let mut args = Vec::new();
+                if self.peek() == Some(&Token::Open) {
+                    self.pos += 1;
+                    args = self.arguments()?;
+                }
+                Ok(Calc::CustomCall { function, name: None, args })
+            },
+            _ => Err(CalcError::UnexpectedToken { offset }),
+        }
+    }
+
+    /* `;` separated arguments up to and including the closing parenthesis. */
+    fn arguments(&mut self) -> Result<Vec<Calc>, CalcError> {
+        let mut args = vec![self.expression(0)?];
+        loop {
+            match self.next()? {
+                (_, Token::Separator) => args.push(self.expression(0)?),
+                (_, Token::Close) => return Ok(args),
+                (offset, _) => return Err(CalcError::UnexpectedToken { offset }),
+            }
+        }
+    }
+
+    fn call(&mut self, function: u16, offset: usize) -> Result<Calc, CalcError> {
+        let mut args = Vec::new();
+        if self.peek() == Some(&Token::Open) {
+            self.pos += 1;
+            args = self.arguments()?;
+        }
+
+        /* Unknown functions and calls with the wrong number of arguments are kept so they can be reported. */
+        match function {
+            FN_IF => {
+                let mut args = args.into_iter().map(Box::new);
+                match (args.next(), args.next(), args.next()) {
+                    (Some(condition), Some(then), otherwise) => Ok(Calc::If { condition, then, otherwise }),
+                    _ => Err(CalcError::UnexpectedToken { offset }),
+                }
+            },
+            FN_CASE => {
+                let default = if args.len() % 2 == 1 { args.pop().map(Box::new) } else { None };
+                let mut args = args.into_iter();
+                let mut arms = Vec::new();
+                while let (Some(test), Some(result)) = (args.next(), args.next()) {
+                    arms.push((test, result));
+                }
+                Ok(Calc::Case { arms, default })
+            },
+            _ => Ok(Calc::Call { function, args }),
+        }
+    }
+
+    fn binding(&mut self) -> Result<(String, Calc), CalcError> {
+        let name = match self.next()? {
+            (_, Token::Name(name)) => name,
+            (offset, _) => return Err(CalcError::UnexpectedToken { offset }),
+        };
+        self.expect(Token::Op(BinaryOp::Equal))?;
+        Ok((name, self.expression(0)?))
+    }
+
+    /* Let ( [ a = 1 ; b = 2 ] ; body ), the brackets are optional for a single binding. */
+    fn let_expression(&mut self) -> Result<Calc, CalcError> {
+        self.expect(Token::Open)?;
+        let mut bindings = Vec::new();
+        if self.peek() == Some(&Token::BracketOpen) {
+            self.pos += 1;
+            bindings.push(self.binding()?);
+            loop {
+                match self.next()? {
+                    (_, Token::Separator) => bindings.push(self.binding()?),
+                    (_, Token::BracketClose) => break,
+                    (offset, _) => return Err(CalcError::UnexpectedToken { offset }),
+                }
+            }
+        } else {
+            bindings.push(self.binding()?);
+        }
+        self.expect(Token::Separator)?;
+        let body = self.expression(0)?;
+        self.expect(Token::Close)?;
+        Ok(Calc::Let { bindings, body: Box::new(body) })
+    }
+}
+
+/// Decode calculation bytecode into an expression tree.
+pub fn decode_calculation(bytecode: &[u8]) -> Result<Calc, CalcError> {
+    let mut parser = Parser { tokens: tokenize(bytecode)?, pos: 0 };
+    let calc = parser.expression(0)?;
+    match parser.tokens.get(parser.pos) {
+        Some((offset, _)) => Err(CalcError::UnexpectedToken { offset: *offset }),
+        None => Ok(calc),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::calc_engine::decode::*;
+    use crate::util::test_util::encrypt;
+
+    fn number(n: u8) -> Vec<u8> {
+        let mut bytes = vec![0x10];
+        bytes.extend([0; 19]);
+        bytes[9] = n;
+        bytes
+    }
+
+    fn name(s: &str) -> Vec<u8> {
+        let mut bytes = vec![0x1a, s.len() as u8];
+        bytes.extend(encrypt(s));
+        bytes
+    }
+
+    fn decode(parts: &[&[u8]]) -> String {
+        decode_calculation(&parts.concat()).unwrap().to_string()
+    }
+
+    #[test]
+    fn operator_precedence() {
+        assert_eq!(decode(&[&name("$x"), &[0x25], &number(2), &[0x27], &number(3)]), "$x + 2 * 3");
+        assert_eq!(decode(&[&[0x04], &name("$x"), &[0x25], &number(2), &[0x05], &[0x27], &number(3)]), "($x + 2) * 3");
+        assert_eq!(decode(&[&[0x54, 0x26], &name("a"), &[0x44], &number(1), &[0x51], &name("b")]), "not -a = 1 and b");
+
+        let calc = decode_calculation(&[name("a"), vec![0x25], number(2), vec![0x27], number(3)].concat()).unwrap();
+        assert!(matches!(calc, Calc::Binary { op: BinaryOp::Add, .. }));
+    }
+
+    #[test]
+    fn special_forms() {
+        assert_eq!(decode(&[&[0x15, 0, 1, 0x04], &name("$x"), &[0x49], &number(1), &[0x03],
+                            &[0x13, 2, 0x3e, 0x3e], &[0x0c, 0x03], &[0x16, 0, 4, 0, 2], &[0x05]]),
+                   "If ( $x > 1 ; \"dd\" ; <unresolved 4:2> )");
+        assert_eq!(decode(&[&[0x15, 0, 3, 0x04, 0x06], &name("a"), &[0x44], &number(1), &[0x03],
+                            &name("b"), &[0x44], &number(2), &[0x07, 0x03], &name("a"), &[0x50], &name("b"), &[0x05]]),
+                   "Let ( [ a = 1 ; b = 2 ] ; a & b )");
+        assert_eq!(decode(&[&[0x15, 0, 2, 0x04], &name("a"), &[0x03], &number(1), &[0x03], &number(2), &[0x05]]),
+                   "Case ( a ; 1 ; 2 )");
+    }
+
+    #[test]
+    fn function_calls() {
+        /* Substitute ( $x ; [ "a" ; "b" ] ) */
+        assert_eq!(decode(&[&[0x15, 0, 0x67, 0x04], &name("$x"), &[0x03, 0x06, 0x13, 1, 0x3b, 0x03, 0x13, 1, 0x38, 0x07, 0x05]]),
+                   "Substitute ( $x ; [ \"a\" ; \"b\" ] )");
+        assert_eq!(decode(&[&[0x15, 0, 0x16, 0x04], &name("AccountName"), &[0x05]]), "Get ( AccountName )");
+        assert_eq!(decode(&[&[0x15, 0, 0x8d], &[0x27, 0x15, 0, 0x92]]), "Random * Pi");
+
+        let unknown = decode_calculation(&[&[0x15, 0x12, 0x34, 0x04][..], &number(1), &[0x05]].concat()).unwrap();
+        assert_eq!(unknown.to_string(), "<unknown function 4660> ( 1 )");
+        assert_eq!(unknown.unknown_functions(), vec![0x1234]);
+
+        let round = decode_calculation(&[&[0x15, 0, 0x8e, 0x04][..], &number(1), &[0x05]].concat()).unwrap();
+        assert_eq!(round.to_string(), "Round ( 1 )");
+        assert_eq!(round.argument_count_mismatches(), vec![("Round", 1)]);
+        assert_eq!(unknown.argument_count_mismatches(), vec![]);
+
+        /* Fact ( n - 1 ) calling custom function 2 */
+        let mut custom = decode_calculation(&[&[0x17, 0, 2, 0x04], &name("n")[..], &[0x26], &number(1), &[0x05]].concat()).unwrap();
+        assert_eq!(custom.to_string(), "<custom function 2> ( n - 1 )");
+        assert_eq!(custom.custom_functions(), vec![2]);
+        custom.resolve_custom_functions(&|id| (id == 2).then(|| "Fact".to_string()));
+        assert_eq!(custom.to_string(), "Fact ( n - 1 )");
+    }
+
+    #[test]
+    fn field_resolution() {
+        /* Total * 2, then If ( <4:2> ; <4:3> ) */
+        let mut calc = decode_calculation(&[&[0x16, 0, 4, 0, 2, 0x27][..], &number(2)].concat()).unwrap();
+        calc.resolve_fields(&|f| (f.field == 2).then(|| "Invoices::Total".to_string()));
+        assert_eq!(calc.to_string(), "Invoices::Total * 2");
+
+        let mut calc = decode_calculation(&[0x15, 0, 1, 0x04, 0x16, 0, 4, 0, 2, 0x03, 0x16, 0, 4, 0, 3, 0x05]).unwrap();
+        calc.resolve_fields(&|f| (f.field == 2).then(|| "Invoices::Total".to_string()));
+        assert_eq!(calc.to_string(), "If ( Invoices::Total ; <unresolved 4:3> )");
+    }
+
+    #[test]
+    fn decode_errors() {
+        assert_eq!(decode_calculation(&[0x13, 5, 0x3e]), Err(CalcError::Truncated { offset: 2 }));
+        assert_eq!(decode_calculation(&[0x04, 0xff]), Err(CalcError::UnknownToken { opcode: 0xff, offset: 1 }));
+        assert_eq!(decode_calculation(&[0x04, 0x25]), Err(CalcError::UnexpectedToken { offset: 1 }));
+        assert_eq!(decode_calculation(&[0x04]), Err(CalcError::UnexpectedEnd));
+    }
+}
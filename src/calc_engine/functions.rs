@@ -0,0 +1,235 @@
+/// A FileMaker built-in calculation function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub id: u16,
+    pub name: &'static str,
+    pub min_args: usize,
+    /// `None` for functions taking any number of arguments.
+    pub max_args: Option<usize>,
+}
+
+impl Function {
+    pub fn accepts(&self, args: usize) -> bool {
+        args >= self.min_args && self.max_args.is_none_or(|max| args <= max)
+    }
+}
+
+pub static FUNCTIONS : &[Function] = &[
+    /* Logical */
+    Function { id: 1, name: "If", min_args: 2, max_args: Some(3) },
+    Function { id: 2, name: "Case", min_args: 2, max_args: None },
+    Function { id: 3, name: "Let", min_args: 2, max_args: Some(2) },
+    Function { id: 4, name: "Choose", min_args: 2, max_args: None },
+    Function { id: 5, name: "Evaluate", min_args: 1, max_args: Some(2) },
+    Function { id: 6, name: "EvaluationError", min_args: 1, max_args: Some(1) },
+    Function { id: 7, name: "GetAsBoolean", min_args: 1, max_args: Some(1) },
+    Function { id: 8, name: "GetField", min_args: 1, max_args: Some(1) },
+    Function { id: 9, name: "GetFieldName", min_args: 1, max_args: Some(1) },
+    Function { id: 10, name: "GetLayoutObjectAttribute", min_args: 2, max_args: Some(4) },
+    Function { id: 11, name: "GetNthRecord", min_args: 2, max_args: Some(2) },
+    Function { id: 12, name: "IsEmpty", min_args: 1, max_args: Some(1) },
+    Function { id: 13, name: "IsValid", min_args: 1, max_args: Some(1) },
+    Function { id: 14, name: "IsValidExpression", min_args: 1, max_args: Some(1) },
+    Function { id: 15, name: "Lookup", min_args: 1, max_args: Some(2) },
+    Function { id: 16, name: "LookupNext", min_args: 2, max_args: Some(2) },
+    Function { id: 17, name: "Quote", min_args: 1, max_args: Some(1) },
+    Function { id: 18, name: "Self", min_args: 0, max_args: Some(0) },
+    Function { id: 19, name: "SetPrecision", min_args: 2, max_args: Some(2) },
+    Function { id: 20, name: "SetRecursion", min_args: 2, max_args: Some(2) },
+    Function { id: 21, name: "ExecuteSQL", min_args: 3, max_args: None },
+    Function { id: 22, name: "Get", min_args: 1, max_args: Some(1) },
+    Function { id: 23, name: "GetSummary", min_args: 2, max_args: Some(2) },
+    Function { id: 24, name: "GetRepetition", min_args: 2, max_args: Some(2) },
+    Function { id: 25, name: "Extend", min_args: 1, max_args: Some(1) },
+    Function { id: 26, name: "Last", min_args: 1, max_args: Some(1) },
+    /* Text */
+    Function { id: 64, name: "Char", min_args: 1, max_args: Some(1) },
+    Function { id: 65, name: "Code", min_args: 1, max_args: Some(1) },
+    Function { id: 66, name: "Exact", min_args: 2, max_args: Some(2) },
+    Function { id: 67, name: "Filter", min_args: 2, max_args: Some(2) },
+    Function { id: 68, name: "FilterValues", min_args: 2, max_args: Some(2) },
+    Function { id: 69, name: "GetAsCSS", min_args: 1, max_args: Some(1) },
+    Function { id: 70, name: "GetAsDate", min_args: 1, max_args: Some(1) },
+    Function { id: 71, name: "GetAsNumber", min_args: 1, max_args: Some(1) },
+    Function { id: 72, name: "GetAsSVG", min_args: 1, max_args: Some(1) },
+    Function { id: 73, name: "GetAsText", min_args: 1, max_args: Some(1) },
+    Function { id: 74, name: "GetAsTime", min_args: 1, max_args: Some(1) },
+    Function { id: 75, name: "GetAsTimestamp", min_args: 1, max_args: Some(1) },
+    Function { id: 76, name: "GetAsURLEncoded", min_args: 1, max_args: Some(1) },
+    Function { id: 77, name: "GetValue", min_args: 2, max_args: Some(2) },
+    Function { id: 78, name: "Hiragana", min_args: 1, max_args: Some(1) },
+    Function { id: 79, name: "KanaHankaku", min_args: 1, max_args: Some(1) },
+    Function { id: 80, name: "KanaZenkaku", min_args: 1, max_args: Some(1) },
+    Function { id: 81, name: "Katakana", min_args: 1, max_args: Some(1) },
+    Function { id: 82, name: "Left", min_args: 2, max_args: Some(2) },
+    Function { id: 83, name: "LeftValues", min_args: 2, max_args: Some(2) },
+    Function { id: 84, name: "LeftWords", min_args: 2, max_args: Some(2) },
+    Function { id: 85, name: "Length", min_args: 1, max_args: Some(1) },
+    Function { id: 86, name: "List", min_args: 1, max_args: None },
+    Function { id: 87, name: "Lower", min_args: 1, max_args: Some(1) },
+    Function { id: 88, name: "Middle", min_args: 3, max_args: Some(3) },
+    Function { id: 89, name: "MiddleValues", min_args: 3, max_args: Some(3) },
+    Function { id: 90, name: "MiddleWords", min_args: 3, max_args: Some(3) },
+    Function { id: 91, name: "NumToJText", min_args: 3, max_args: Some(3) },
+    Function { id: 92, name: "PatternCount", min_args: 2, max_args: Some(2) },
+    Function { id: 93, name: "Position", min_args: 4, max_args: Some(4) },
+    Function { id: 94, name: "Proper", min_args: 1, max_args: Some(1) },
+    Function { id: 95, name: "Replace", min_args: 4, max_args: Some(4) },
+    Function { id: 96, name: "Right", min_args: 2, max_args: Some(2) },
+    Function { id: 97, name: "RightValues", min_args: 2, max_args: Some(2) },
+    Function { id: 98, name: "RightWords", min_args: 2, max_args: Some(2) },
+    Function { id: 99, name: "RomanHankaku", min_args: 1, max_args: Some(1) },
+    Function { id: 100, name: "RomanZenkaku", min_args: 1, max_args: Some(1) },
+    Function { id: 101, name: "SerialIncrement", min_args: 2, max_args: Some(2) },
+    Function { id: 102, name: "SortValues", min_args: 1, max_args: Some(3) },
+    Function { id: 103, name: "Substitute", min_args: 2, max_args: None },
+    Function { id: 104, name: "Trim", min_args: 1, max_args: Some(1) },
+    Function { id: 105, name: "TrimAll", min_args: 3, max_args: Some(3) },
+    Function { id: 106, name: "UniqueValues", min_args: 1, max_args: Some(3) },
+    Function { id: 107, name: "Upper", min_args: 1, max_args: Some(1) },
+    Function { id: 108, name: "ValueCount", min_args: 1, max_args: Some(1) },
+    Function { id: 109, name: "WordCount", min_args: 1, max_args: Some(1) },
+    Function { id: 110, name: "RGB", min_args: 3, max_args: Some(3) },
+    Function { id: 111, name: "TextColor", min_args: 2, max_args: Some(2) },
+    Function { id: 112, name: "TextColorRemove", min_args: 1, max_args: Some(2) },
+    Function { id: 113, name: "TextFont", min_args: 2, max_args: Some(3) },
+    Function { id: 114, name: "TextFontRemove", min_args: 1, max_args: Some(3) },
+    Function { id: 115, name: "TextFormatRemove", min_args: 1, max_args: Some(1) },
+    Function { id: 116, name: "TextSize", min_args: 2, max_args: Some(2) },
+    Function { id: 117, name: "TextSizeRemove", min_args: 1, max_args: Some(2) },
+    Function { id: 118, name: "TextStyleAdd", min_args: 2, max_args: Some(2) },
+    Function { id: 119, name: "TextStyleRemove", min_args: 2, max_args: Some(2) },
+    Function { id: 120, name: "Base64Decode", min_args: 1, max_args: Some(2) },
+    Function { id: 121, name: "Base64Encode", min_args: 1, max_args: Some(1) },
+    Function { id: 122, name: "Base64EncodeRFC", min_args: 2, max_args: Some(2) },
+    Function { id: 123, name: "HexDecode", min_args: 1, max_args: Some(2) },
+    Function { id: 124, name: "HexEncode", min_args: 1, max_args: Some(1) },
+    Function { id: 125, name: "CryptDigest", min_args: 2, max_args: Some(2) },
+    Function { id: 126, name: "CryptAuthCode", min_args: 3, max_args: Some(3) },
+    Function { id: 127, name: "TextEncode", min_args: 3, max_args: Some(3) },
+    Function { id: 128, name: "TextDecode", min_args: 2, max_args: Some(2) },
+    /* Number */
+    Function { id: 129, name: "Abs", min_args: 1, max_args: Some(1) },
+    Function { id: 130, name: "Ceiling", min_args: 1, max_args: Some(1) },
+    Function { id: 131, name: "Combination", min_args: 2, max_args: Some(2) },
+    Function { id: 132, name: "Div", min_args: 2, max_args: Some(2) },
+    Function { id: 133, name: "Exp", min_args: 1, max_args: Some(1) },
+    Function { id: 134, name: "Factorial", min_args: 1, max_args: Some(2) },
+    Function { id: 135, name: "Floor", min_args: 1, max_args: Some(1) },
+    Function { id: 136, name: "Int", min_args: 1, max_args: Some(1) },
+    Function { id: 137, name: "Lg", min_args: 1, max_args: Some(1) },
+    Function { id: 138, name: "Ln", min_args: 1, max_args: Some(1) },
+    Function { id: 139, name: "Log", min_args: 1, max_args: Some(1) },
+    Function { id: 140, name: "Mod", min_args: 2, max_args: Some(2) },
+    Function { id: 141, name: "Random", min_args: 0, max_args: Some(0) },
+    Function { id: 142, name: "Round", min_args: 2, max_args: Some(2) },
+    Function { id: 143, name: "Sign", min_args: 1, max_args: Some(1) },
+    Function { id: 144, name: "Sqrt", min_args: 1, max_args: Some(1) },
+    Function { id: 145, name: "Truncate", min_args: 2, max_args: Some(2) },
+    Function { id: 146, name: "Pi", min_args: 0, max_args: Some(0) },
+    Function { id: 147, name: "Sin", min_args: 1, max_args: Some(1) },
+    Function { id: 148, name: "Cos", min_args: 1, max_args: Some(1) },
+    Function { id: 149, name: "Tan", min_args: 1, max_args: Some(1) },
+    Function { id: 150, name: "Asin", min_args: 1, max_args: Some(1) },
+    Function { id: 151, name: "Acos", min_args: 1, max_args: Some(1) },
+    Function { id: 152, name: "Atan", min_args: 1, max_args: Some(1) },
+    Function { id: 153, name: "Degrees", min_args: 1, max_args: Some(1) },
+    Function { id: 154, name: "Radians", min_args: 1, max_args: Some(1) },
+    Function { id: 155, name: "FV", min_args: 3, max_args: Some(3) },
+    Function { id: 156, name: "NPV", min_args: 2, max_args: Some(2) },
+    Function { id: 157, name: "PMT", min_args: 3, max_args: Some(3) },
+    Function { id: 158, name: "PV", min_args: 3, max_args: Some(3) },
+    /* Date and time */
+    Function { id: 192, name: "Date", min_args: 3, max_args: Some(3) },
+    Function { id: 193, name: "Day", min_args: 1, max_args: Some(1) },
+    Function { id: 194, name: "DayName", min_args: 1, max_args: Some(1) },
+    Function { id: 195, name: "DayNameJ", min_args: 1, max_args: Some(1) },
+    Function { id: 196, name: "DayOfWeek", min_args: 1, max_args: Some(1) },
+    Function { id: 197, name: "DayOfYear", min_args: 1, max_args: Some(1) },
+    Function { id: 198, name: "Month", min_args: 1, max_args: Some(1) },
+    Function { id: 199, name: "MonthName", min_args: 1, max_args: Some(1) },
+    Function { id: 200, name: "MonthNameJ", min_args: 1, max_args: Some(1) },
+    Function { id: 201, name: "WeekOfYear", min_args: 1, max_args: Some(1) },
+    Function { id: 202, name: "WeekOfYearFiscal", min_args: 2, max_args: Some(2) },
+    Function { id: 203, name: "Year", min_args: 1, max_args: Some(1) },
+    Function { id: 204, name: "YearName", min_args: 2, max_args: Some(2) },
+    Function { id: 205, name: "Time", min_args: 3, max_args: Some(3) },
+    Function { id: 206, name: "Hour", min_args: 1, max_args: Some(1) },
+    Function { id: 207, name: "Minute", min_args: 1, max_args: Some(1) },
+    Function { id: 208, name: "Seconds", min_args: 1, max_args: Some(1) },
+    Function { id: 209, name: "Timestamp", min_args: 2, max_args: Some(2) },
+    /* Aggregate */
+    Function { id: 256, name: "Average", min_args: 1, max_args: None },
+    Function { id: 257, name: "Count", min_args: 1, max_args: None },
+    Function { id: 258, name: "Max", min_args: 1, max_args: None },
+    Function { id: 259, name: "Min", min_args: 1, max_args: None },
+    Function { id: 260, name: "StDev", min_args: 1, max_args: None },
+    Function { id: 261, name: "StDevP", min_args: 1, max_args: None },
+    Function { id: 262, name: "Sum", min_args: 1, max_args: None },
+    Function { id: 263, name: "Variance", min_args: 1, max_args: None },
+    Function { id: 264, name: "VarianceP", min_args: 1, max_args: None },
+    /* JSON */
+    Function { id: 320, name: "JSONDeleteElement", min_args: 2, max_args: Some(2) },
+    Function { id: 321, name: "JSONFormatElements", min_args: 1, max_args: Some(1) },
+    Function { id: 322, name: "JSONGetElement", min_args: 2, max_args: Some(2) },
+    Function { id: 323, name: "JSONGetElementType", min_args: 2, max_args: Some(2) },
+    Function { id: 324, name: "JSONListKeys", min_args: 2, max_args: Some(2) },
+    Function { id: 325, name: "JSONListValues", min_args: 2, max_args: Some(2) },
+    Function { id: 326, name: "JSONMakeArray", min_args: 3, max_args: Some(3) },
+    Function { id: 327, name: "JSONSetElement", min_args: 2, max_args: None },
+    /* Container */
+    Function { id: 384, name: "GetContainerAttribute", min_args: 2, max_args: Some(2) },
+    Function { id: 385, name: "GetHeight", min_args: 1, max_args: Some(1) },
+    Function { id: 386, name: "GetWidth", min_args: 1, max_args: Some(1) },
+    Function { id: 387, name: "GetThumbnail", min_args: 3, max_args: Some(3) },
+    Function { id: 388, name: "VerifyContainer", min_args: 1, max_args: Some(1) },
+    /* Design */
+    Function { id: 448, name: "DatabaseNames", min_args: 0, max_args: Some(0) },
+    Function { id: 449, name: "FieldBounds", min_args: 3, max_args: Some(3) },
+    Function { id: 450, name: "FieldComment", min_args: 2, max_args: Some(2) },
+    Function { id: 451, name: "FieldIDs", min_args: 2, max_args: Some(2) },
+    Function { id: 452, name: "FieldNames", min_args: 2, max_args: Some(2) },
+    Function { id: 453, name: "FieldRepetitions", min_args: 3, max_args: Some(3) },
+    Function { id: 454, name: "FieldStyle", min_args: 3, max_args: Some(3) },
+    Function { id: 455, name: "FieldType", min_args: 2, max_args: Some(2) },
+    Function { id: 456, name: "GetNextSerialValue", min_args: 2, max_args: Some(2) },
+    Function { id: 457, name: "LayoutIDs", min_args: 1, max_args: Some(1) },
+    Function { id: 458, name: "LayoutNames", min_args: 1, max_args: Some(1) },
+    Function { id: 459, name: "LayoutObjectNames", min_args: 2, max_args: Some(2) },
+    Function { id: 460, name: "RelationInfo", min_args: 2, max_args: Some(2) },
+    Function { id: 461, name: "ScriptIDs", min_args: 1, max_args: Some(1) },
+    Function { id: 462, name: "ScriptNames", min_args: 1, max_args: Some(1) },
+    Function { id: 463, name: "TableIDs", min_args: 1, max_args: Some(1) },
+    Function { id: 464, name: "TableNames", min_args: 1, max_args: Some(1) },
+    Function { id: 465, name: "ValueListIDs", min_args: 1, max_args: Some(1) },
+    Function { id: 466, name: "ValueListItems", min_args: 2, max_args: Some(2) },
+    Function { id: 467, name: "ValueListNames", min_args: 1, max_args: Some(1) },
+    Function { id: 468, name: "WindowNames", min_args: 0, max_args: Some(1) },
+];
+
+pub const FN_IF : u16 = 1;
+pub const FN_CASE : u16 = 2;
+pub const FN_LET : u16 = 3;
+
+/// Look up a function by its calculation bytecode id.
+pub fn get_function(id: u16) -> Option<&'static Function> {
+    FUNCTIONS.iter().find(|f| f.id == id)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::calc_engine::functions::*;
+
+    #[test]
+    fn function_ids_unique() {
+        let mut seen = HashSet::new();
+        for f in FUNCTIONS {
+            assert!(seen.insert(f.id), "function id {} is used twice ({})", f.id, f.name);
+        }
+        assert_eq!(get_function(128).map(|f| f.name), Some("TextDecode"));
+        assert_eq!(get_function(129).map(|f| f.name), Some("Abs"));
+        assert!(get_function(129).unwrap().accepts(1));
+        assert!(get_function(326).is_some_and(|f| f.name == "JSONMakeArray" && f.accepts(3) && !f.accepts(2)));
+    }
+}
@@ -0,0 +1,4 @@
+pub mod calc;
+pub mod decode;
+pub mod functions;
+pub mod number;
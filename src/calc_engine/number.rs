@@ -0,0 +1,160 @@
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// An exact decimal number, `mantissa * 10^exponent`.
+///
+/// Trailing zeros written by the author are kept, so `1.50` prints back as `1.50`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Decimal {
+    negative: bool,
+    /* Decimal digits without leading zeros, empty for zero. */
+    digits: String,
+    exponent: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDecimalError;
+
+impl fmt::Display for ParseDecimalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid decimal number")
+    }
+}
+
+impl std::error::Error for ParseDecimalError {}
+
+impl Decimal {
+    fn from_digits(negative: bool, digits: &str, exponent: i32) -> Decimal {
+        let digits = digits.trim_start_matches('0').to_string();
+        Decimal { negative: negative && !digits.is_empty(), digits, exponent }
+    }
+
+    pub fn new(negative: bool, mantissa: u128, exponent: i32) -> Decimal {
+        Decimal::from_digits(negative, &mantissa.to_string(), exponent)
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// The digits of the mantissa, `"0"` for zero.
+    pub fn mantissa(&self) -> &str {
+        if self.digits.is_empty() { "0" } else { &self.digits }
+    }
+
+    pub fn exponent(&self) -> i32 {
+        self.exponent
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let digits = self.mantissa();
+        if self.exponent >= 0 {
+            if self.digits.is_empty() {
+                return write!(f, "0");
+            }
+            return write!(f, "{}{}", digits, "0".repeat(self.exponent as usize));
+        }
+
+        let fraction = self.exponent.unsigned_abs() as usize;
+        if digits.len() > fraction {
+            let (whole, part) = digits.split_at(digits.len() - fraction);
+            write!(f, "{}.{}", whole, part)
+        } else {
+            write!(f, "0.{}{}", "0".repeat(fraction - digits.len()), digits)
+        }
+    }
+}
+
+/* Accepts `-12.50`, `.5`, `3e-2` and the like. */
+impl FromStr for Decimal {
+    type Err = ParseDecimalError;
+
+    fn from_str(s: &str) -> Result<Decimal, ParseDecimalError> {
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let (number, exponent) = match s.find(['e', 'E']) {
+            Some(at) => (&s[..at], s[at + 1..].parse::<i32>().map_err(|_| ParseDecimalError)?),
+            None => (s, 0),
+        };
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+        let digits = format!("{}{}", whole, fraction);
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseDecimalError);
+        }
+        let exponent = exponent.checked_sub(fraction.len() as i32).ok_or(ParseDecimalError)?;
+        Ok(Decimal::from_digits(negative, &digits, exponent))
+    }
+}
+
+impl Serialize for Decimal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Decimal, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Decode the 19 bytes following a `0x10` number token.
+///
+/// Byte 0 holds the sign, bytes 2-3 a little endian base 10 exponent and
+/// bytes 8-18 the mantissa as a little endian unsigned integer.
+pub fn decode_number(bytes: &[u8; 19]) -> Decimal {
+    let exponent = i16::from_le_bytes([bytes[2], bytes[3]]) as i32;
+    let mantissa = bytes[8..].iter().rev().fold(0u128, |acc, b| (acc << 8) | *b as u128);
+    Decimal::new(bytes[0] & 0x01 != 0, mantissa, exponent)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::calc_engine::number::*;
+
+    #[test]
+    fn decimal_round_trip() {
+        for s in ["0", "3.14", "-2", "1000000", "1.50", "0.05", "-0.001", "123456789012345678901234567890.123456789"] {
+            assert_eq!(s.parse::<Decimal>().unwrap().to_string(), s);
+        }
+        assert_eq!("1e3".parse::<Decimal>().unwrap().to_string(), "1000");
+        assert_eq!("-0".parse::<Decimal>().unwrap().to_string(), "0");
+        assert_eq!(".5".parse::<Decimal>().unwrap().to_string(), "0.5");
+        assert!("1.2.3".parse::<Decimal>().is_err());
+        assert!("".parse::<Decimal>().is_err());
+
+        let d = "-12.5".parse::<Decimal>().unwrap();
+        assert_eq!(serde_json::to_string(&d).unwrap(), "\"-12.5\"");
+        assert_eq!(serde_json::from_str::<Decimal>("\"-12.5\"").unwrap(), d);
+    }
+
+    #[test]
+    fn number_decoding() {
+        let mut bytes = [0u8; 19];
+        bytes[8] = 0x3a;
+        bytes[2..4].copy_from_slice(&(-2i16).to_le_bytes());
+        assert_eq!(decode_number(&bytes).to_string(), "0.58");
+
+        let mut bytes = [0u8; 19];
+        bytes[0] = 1;
+        bytes[8..10].copy_from_slice(&314u16.to_le_bytes());
+        bytes[2..4].copy_from_slice(&(-2i16).to_le_bytes());
+        assert_eq!(decode_number(&bytes).to_string(), "-3.14");
+
+        let mut bytes = [0u8; 19];
+        bytes[8..12].copy_from_slice(&1_000_000u32.to_le_bytes());
+        assert_eq!(decode_number(&bytes).to_string(), "1000000");
+
+        let mut bytes = [0xffu8; 19];
+        bytes[0..8].copy_from_slice(&[0; 8]);
+        assert_eq!(decode_number(&bytes).to_string(), ((1u128 << 88) - 1).to_string());
+    }
+}
@@ -1,596 +1,1027 @@
 use std::fs::File;
-use std::io::Read;
+use std::io::{BufReader, Cursor, ErrorKind, Read, Seek, SeekFrom};
 use std::path::Path;
-use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
+use crate::calc_engine::calc::{Calc, FieldRef};
+use crate::calc_engine::decode::{decode_calculation, CalcError};
+use crate::error::FmpError;
 use crate::repr::component;
-use crate::script_engine::instructions::{ScriptStep, INSTRUCTIONMAP, Instruction};
-use crate::decompile::decompiler;
+use crate::decompile::steps::{decode_step_params, StepOptions};
+use crate::script_engine::instructions::{ScriptStep, INSTRUCTIONMAP};
+use crate::script_engine::step_params::StepParams;
+use crate::repr::custom_function::{decode_parameters, Availability};
+use crate::repr::data_source::{decode_paths, BaseTable, DataSourceType};
+use crate::repr::field::{decode_field_summary, decode_field_switches};
+use crate::repr::layout::{build_object_tree, decode_layout_switches, decode_object_switches, decode_part, FieldTarget, LayoutObject, LayoutPart};
+use crate::repr::relationship::{decode_join_options, decode_predicates};
+use crate::repr::security::{decode_account_switches, decode_privilege_set_ids, decode_privilege_set_switches, decode_record_access, AccessLevel};
+use crate::repr::script_tree::{build_script_tree, decode_catalog_switches, folder_path, CatalogEntry, CatalogKind};
+use crate::repr::trigger::{decode_trigger, TriggerEvent, TriggerSource};
+use crate::repr::value_list::{decode_custom_values, decode_value_list_field, decode_value_list_switches, ValueListSource};
 use crate::repr::file::FmpFile;
-use crate::fmp_format::{sector, chunk::{get_chunk_from_code, ChunkType}, metadata_constants};
+use crate::repr::record::RecordSource;
+use crate::fmp_format::{header::{get_header, FileHeader}, sector::{self, SECTOR_SIZE}, chunk::{get_chunk_from_code, ChunkType}, metadata_constants};
 
-use crate::util::format_decode::{fm_string_decrypt, get_path_int};
-
-const SECTOR_SIZE : usize = 4096;
+use crate::util::format_decode::{fm_string_decrypt, get_int, get_path_int};
 
+/* Script step data found while walking, applied once every script has been assembled. */
+struct StepData {
+    script: usize,
+    step: usize,
+    /* Path below the step directory. */
+    path: Vec<usize>,
+    key: u16,
+    data: Vec<u8>,
+}
 
-fn decompile_calculation(bytecode: &[u8]) -> String {
-    println!("Bytecode we sedn: {:x?}",  bytecode);
-    let mut it = bytecode.iter().peekable();
-    let mut result = String::new();
+/* Calculation field formulas, decoded once every field they may reference is known. */
+struct FieldCalculation {
+    table: usize,
+    field: u16,
+    /* Directory below the field: 5 formula, 6 auto-enter, 7 validation. */
+    slot: usize,
+    data: Vec<u8>,
+}
 
-    while let Some(c) = it.next() {
-        match c {
-            0x4 => {
-                result.push('(');
-            }
-            0x5 => {
-                result.push(')');
-            }
-            0x10 => {
-                /* decode number */
-                for i in 0..19 {
-                    let cur = it.next();
-                    if i == 8 {
-                        result.push_str(&cur.unwrap().to_string());
-                    }
-                }
-            },
-            0x13 => {
-                /* Processing String */
-                let n = it.next();
-                let mut s = String::new();
-                for i in 1..=*n.unwrap() as usize {
-                    s.push(*it.next().unwrap() as char);
-                }
-                let mut text = String::new();
-                text.push('"');
-                text.push_str(&fm_string_decrypt(s.as_bytes()));
-                text.push('"');
-                result.push_str(&text);
-            }
-            0x1a => {
-                /* decode variable */
-                let n = it.next();
-                let mut name_arr = String::new();
-                for i in 1..=*n.unwrap() as usize {
-                    name_arr.push(*it.next().unwrap() as char);
-                }
-                let name = fm_string_decrypt(name_arr.as_bytes());
-                result.push_str(&name);
-            },
-            0x25 => {
-                result.push('+');
-            }
-            0x26 => {
-                result.push('-');
-            }
-            0x27 => {
-                result.push('*');
-            }
-            0x28 => {
-                result.push('/');
-            },
-            0x41 => {
-                result.push('<');
-            }
-            0x43 => {
-                result.push_str("<=");
-            }
-            0x44 => {
-                result.push_str("==");
-            }
-            0x46 => {
-                result.push_str("!=");
-            }
-            0x47 => {
-                result.push_str(">=");
-            }
-            0x49 => {
-                result.push('>');
-            }
-            0x50 => {
-                result.push('&');
-            }
-            0xC => {
-                result.push(' ');
-            }
-            _ => {
+/* Switches and parameter calculation of a trigger or button action. */
+#[derive(Default)]
+struct TriggerData {
+    switches: Vec<u8>,
+    parameter: Vec<u8>,
+}
 
-            }
+impl TriggerData {
+    fn set(&mut self, key: Option<u16>, data: &[u8]) {
+        match key {
+            Some(metadata_constants::LAYOUT_SWITCHES) => self.switches = data.to_vec(),
+            Some(metadata_constants::CALCULATION) => self.parameter = data.to_vec(),
+            _ => {},
         }
+    }
+}
 
+/* What a path below a layout's object directory points into. */
+enum ObjectEntry {
+    Object,
+    Trigger(usize),
+    Action,
+}
+
+/* Splits the path below a layout's object directory into the object ids, outermost first,
+ * and the trigger or button action directory if the path is inside one. */
+fn split_object_path(rest: &[usize]) -> Option<(Vec<usize>, ObjectEntry)> {
+    let mut objects = Vec::new();
+    let mut i = 0;
+    loop {
+        objects.push(*rest.get(i)?);
+        match rest.get(i + 1) {
+            None => return Some((objects, ObjectEntry::Object)),
+            Some(3) => i += 2,
+            Some(6) if rest.len() == i + 3 => return Some((objects, ObjectEntry::Trigger(rest[i + 2]))),
+            Some(8) if rest.len() == i + 2 => return Some((objects, ObjectEntry::Action)),
+            _ => return None,
+        }
     }
+}
+
+/* Decode a calculation, naming the fields and custom functions it refers to. */
+pub(crate) fn decode_resolved(bytecode: &[u8], file: &FmpFile) -> Result<Calc, CalcError> {
+    let mut calc = decode_calculation(bytecode)?;
+    calc.resolve_fields(&|field| file.field_reference_name(field.table_occurrence, field.field));
+    calc.resolve_custom_functions(&|id| file.custom_functions.get(&id).map(|f| f.name.clone()));
+    Ok(calc)
+}
+
+/* Field targets of steps such as Set Field use the same ids as a calculation's field reference. */
+pub(crate) fn decode_field_reference(data: &[u8], file: &FmpFile) -> FieldRef {
+    let table_occurrence = data.get(0..2).map_or(0, get_int);
+    let field = data.get(2..4).map_or(0, get_int);
+    FieldRef { table_occurrence, field, name: file.field_reference_name(table_occurrence, field) }
+}
 
-    println!("Found calculation: {}", result);
-    return result;
+/* Script code is stored as an array of 28 byte steps. */
+fn decode_steps(code: &[u8]) -> Vec<ScriptStep> {
+    code.chunks_exact(28)
+        .filter_map(|ins| {
+            INSTRUCTIONMAP[ins[21] as usize].clone().map(|opcode| ScriptStep {
+                opcode,
+                index: get_path_int(&[ins[2], ins[3]]),
+                params: StepParams::None,
+                disabled: ins[1] & 0x01 == 0,
+            })
+        })
+        .collect()
 }
 
-pub fn decompile_fmp12_file_with_header(path: &Path) -> FmpFile {
-    let mut file = File::open(path).expect("unable to open file.");
-    let mut buffer = Vec::<u8>::new();
-    file.read_to_end(&mut buffer).expect("Unable to read file.");
+/* Read sector `idx` into `buffer`, reporting how far a short read got. */
+pub(crate) fn read_sector<R: Read + Seek>(reader: &mut R, idx: usize, buffer: &mut [u8; SECTOR_SIZE]) -> Result<(), FmpError> {
+    reader.seek(SeekFrom::Start((idx * SECTOR_SIZE) as u64))?;
+    let mut filled = 0;
+    while filled < SECTOR_SIZE {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => return Err(FmpError::TruncatedSector { sector: idx, offset: filled }),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {},
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
 
-    println!("Found: {:?}", &buffer[0..4095]);
+/// Read and validate only the file header, without walking the rest of the file.
+pub fn read_fmp12_header<R: Read + Seek>(mut reader: R) -> Result<FileHeader, FmpError> {
+    let mut buffer = [0u8; SECTOR_SIZE];
+    read_sector(&mut reader, 0, &mut buffer).map_err(|e| match e {
+        FmpError::TruncatedSector { .. } => FmpError::BadHeader("file is smaller than a header sector"),
+        e => e,
+    })?;
+    get_header(&buffer)
+}
+
+/// Decompile the file at `path`, the same as [`decompile_fmp12_file`].
+#[deprecated(note = "decompile_fmp12_file already rejects a file from its header alone, use it instead")]
+pub fn decompile_fmp12_file_with_header(path: &Path) -> Result<FmpFile, FmpError> {
     decompile_fmp12_file(path)
 }
 
-pub fn decompile_fmp12_file(path: &Path) -> FmpFile {
-    
-    let mut file = File::open(path).expect("unable to open file.");
-    let mut fmp_file = FmpFile::new();
-    let mut buffer = Vec::<u8>::new();
-    file.read_to_end(&mut buffer).expect("Unable to read file.");
+/// Decompile the file at `path`, rejecting it from its header alone before any other
+/// sector is read. The file is reopened when its records are read.
+pub fn decompile_fmp12_file(path: &Path) -> Result<FmpFile, FmpError> {
+    let source = RecordSource::path(path.to_path_buf())?;
+    let mut fmp_file = decompile_fmp12_reader(BufReader::new(File::open(path)?))?;
+    fmp_file.source = source;
+    Ok(fmp_file)
+}
 
-    let mut offset = SECTOR_SIZE;
-    let mut sectors = Vec::<sector::Sector>::new();
+/// Decompile a file held in memory. A copy of `bytes` is kept to read records from.
+pub fn decompile_fmp12_bytes(bytes: &[u8]) -> Result<FmpFile, FmpError> {
+    let mut fmp_file = decompile_fmp12_reader(Cursor::new(bytes))?;
+    fmp_file.source = RecordSource::Bytes(Arc::from(bytes));
+    Ok(fmp_file)
+}
 
-    let first = sector::get_sector(&buffer[offset..]);
-    let n_blocks = first.next;
+/// Decompile an FMP12 file from any seekable source. Sectors are read one at
+/// a time as the sector chain is followed, so the whole file is never held in memory.
+/// The reader is not kept, records can only be read from files decompiled from a path or bytes.
+pub fn decompile_fmp12_reader<R: Read + Seek>(mut reader: R) -> Result<FmpFile, FmpError> {
+    let mut fmp_file = FmpFile::new();
+    let mut buffer = [0u8; SECTOR_SIZE];
 
-    sectors.resize(n_blocks + 1, sector::Sector { 
-        deleted: false, 
-        level: 0,
-        previous: 0,
-        next: 0,
-        payload: &[0],
-        chunks: vec![] 
-    });
+    fmp_file.header = read_fmp12_header(&mut reader)?;
+    fmp_file.name = fmp_file.header.original_filename.clone();
 
-    sectors[0] = first;
     let mut idx = 2;
-    let mut script_segments: HashMap<usize, BTreeMap<usize, Vec<u8>>> = HashMap::new();
-
+    let mut visited = HashSet::<usize>::new();
+    let mut script_segments: BTreeMap<usize, BTreeMap<usize, Vec<u8>>> = BTreeMap::new();
+    let mut step_data = Vec::<StepData>::new();
+    let mut field_calculations = Vec::<FieldCalculation>::new();
+    /* By table and field, applied once every key of the field is read */
+    let mut field_switches = BTreeMap::<(usize, u16), Vec<u8>>::new();
+    let mut custom_function_bodies = BTreeMap::<usize, Vec<u8>>::new();
+    let mut layout_parts: BTreeMap<usize, BTreeMap<usize, LayoutPart>> = BTreeMap::new();
+    let mut layout_objects: BTreeMap<usize, BTreeMap<Vec<usize>, LayoutObject>> = BTreeMap::new();
+    /* By layout, object path (empty for the layout itself) and trigger directory, `None` for a button action. */
+    let mut layout_triggers: BTreeMap<(usize, Vec<usize>, Option<usize>), TriggerData> = BTreeMap::new();
+    let mut file_triggers: BTreeMap<usize, TriggerData> = BTreeMap::new();
+    let mut script_catalog: BTreeMap<usize, CatalogEntry> = BTreeMap::new();
+    /* Short relationship entries belong to the table occurrence defined just before them */
+    let mut last_occurrence: Option<usize> = None;
 
     while idx != 0 {
-        let start = idx * SECTOR_SIZE;
-        let bound = start + SECTOR_SIZE;
-        offset = start;
-
-        sectors[idx] = sector::get_sector(&buffer[offset..]);
-        let mut path = Vec::<String>::new();
-        offset += 20;
-        while offset < bound {
-            let chunk = get_chunk_from_code(&buffer, 
-                                            &mut offset, 
-                                            &mut path, 
-                                            start).expect("Unable to decode chunk.");          
-            match &path.iter().map(|s| s.as_str()).collect::<Vec<_>>().as_slice() {
+        if !visited.insert(idx) {
+            return Err(FmpError::InvalidSectorChain { sector: idx });
+        }
+        read_sector(&mut reader, idx, &mut buffer)?;
+
+        let current = sector::get_sector(&buffer, idx)?;
+        let mut path = Vec::<usize>::new();
+        let mut offset = 20;
+        while offset < SECTOR_SIZE {
+            let chunk = get_chunk_from_code(&buffer,
+                                            &mut offset,
+                                            &mut path,
+                                            idx)?;
+            match path.as_slice() {
                 /* Examining relatinoships of table occurences */
-                ["3", "17", "5", "0", "251"] => {
-                    if chunk.ctype == ChunkType::DataSimple {
+                [3, 17, 5, 0, 251] => {
+                    if let (ChunkType::DataSimple, Some(data)) = (&chunk.ctype, chunk.data) {
                         let mut tmp = component::FMComponentRelationship::new();
-                        tmp.table1 = fmp_file.table_occurrences.len() as u16;
-                        tmp.table2 = chunk.data.unwrap()[2] as u16;
+                        /* Short entries only carry the second occurrence, the first is the one just defined. */
+                        tmp.table1 = data.get(3..5).map(get_int).or(last_occurrence).map(|id| id as u16);
+                        tmp.table2 = data.get(1..3).map_or(0, get_int) as u16;
                         fmp_file.relationships.insert(fmp_file.relationships.len(), tmp);
-                        // println!("Path: {:?}. reference: {:?}, ref_data: {:?}", 
-                        //      &path.clone(),
-                        //      chunk.ref_simple,
-                        //      chunk.data);
+                    }
+                },
+                [3, 17, 5, 0, key @ (252 | 253)] => {
+                    if let (ChunkType::DataSimple, Some(data)) = (&chunk.ctype, chunk.data) {
+                        let n = fmp_file.relationships.len().wrapping_sub(1);
+                        let Some(relationship) = fmp_file.relationships.get_mut(&n) else {
+                            fmp_file.diagnostics.push(FmpError::DanglingReference { component: "relationship", id: n });
+                            continue;
+                        };
+                        if *key == 252 {
+                            relationship.predicates = decode_predicates(data);
+                        } else {
+                            (relationship.table1_options, relationship.table2_options) = decode_join_options(data);
+                        }
                     }
                 },
                 /* Examining table occurences */
-                ["3", "17", "5", "0", ..] => {
+                [3, 17, 5, 0, ..] => {
                     let s = fm_string_decrypt(chunk.data.unwrap_or(&[0]));
                     match chunk.ref_simple {
                         Some(2) => {
-                            let tmp = component::FMComponentTableOccurence {
-                                table_occurence_name: String::new(),
-                                create_by_user: String::new(),
-                                created_by_account: String::new(),
-                                table_actual: chunk.data.unwrap()[6] as u16,
-                                table_actual_name: String::new(),
-                            };
-                            fmp_file.table_occurrences.insert(fmp_file.table_occurrences.len() + 1, tmp);
+                            let mut tmp = component::FMComponentTableOccurence::new();
+                            tmp.table_actual = chunk.data.and_then(|d| d.get(6)).copied().unwrap_or(0) as u16;
+                            tmp.data_source = chunk.data
+                                .and_then(|d| d.get(8..10))
+                                .map(|source| get_int(source) as u16)
+                                .filter(|source| *source != 0);
+                            let id = fmp_file.table_occurrences.len() + 1;
+                            fmp_file.table_occurrences.insert(id, tmp);
+                            last_occurrence = Some(id);
                         }
                         Some(16) => {
-                            fmp_file.table_occurrences
-                                .get_mut(&(fmp_file.table_occurrences.len())).unwrap()
-                                .table_occurence_name = s;
-                        },
-                        Some(129) => {
-                        },
-                        Some(130) => {
-                        },
-                        Some(131) => {
-                        },
-                        _ => {
-                            let s = fm_string_decrypt(chunk.data.unwrap_or(&[0]));
-                            if s.is_empty() {
-                                continue;
-                            }
-                            if chunk.ctype == ChunkType::PathPush 
-                                || chunk.ctype == ChunkType::PathPop 
-                                || chunk.ctype == ChunkType::Noop {
-
-                            } else {
+                            let n = fmp_file.table_occurrences.len();
+                            match fmp_file.table_occurrences.get_mut(&n) {
+                                Some(occurrence) => occurrence.table_occurence_name = s,
+                                None => fmp_file.diagnostics.push(FmpError::DanglingReference { component: "table occurrence", id: n }),
                             }
-                        }
+                        },
+                        _ => {}
                     }
                 },
-                ["4", "5", ..] => {
-                    let s = fm_string_decrypt(chunk.data.unwrap_or(&[0]));
-                    if chunk.ref_simple.is_some() {
-                        // println!("Path: {:?}. reference: {:?}, ref_data: {:?}, data: {:?}", 
-                        //      &path.clone(),
-                        //      chunk.ref_simple,
-                        //      chunk.ref_data,
-                        //      chunk.data,
-                        //      );
+                /* Examing layouts */
+                [4, 1, 7, x] => {
+                    let data = chunk.data.unwrap_or(&[]);
+                    let layout = fmp_file.layouts.entry(*x).or_default();
+                    match chunk.ref_simple {
+                        Some(metadata_constants::COMPONENT_NAME) => layout.layout_name = fm_string_decrypt(data),
+                        Some(metadata_constants::LAYOUT_SWITCHES) => decode_layout_switches(layout, data),
+                        _ => {},
                     }
                 },
-                /* Examing layouts */
-                ["4", "1", "7", x, ..] => {
-                    let s = fm_string_decrypt(chunk.data.unwrap_or(&[0]));
+                [4, 1, 7, x, 2, part] if chunk.ref_simple == Some(metadata_constants::LAYOUT_SWITCHES) => {
+                    layout_parts.entry(*x).or_default().insert(*part, decode_part(chunk.data.unwrap_or(&[])));
+                },
+                /* Examining layout triggers */
+                [4, 1, 7, x, 6, n] => {
+                    layout_triggers.entry((*x, vec![], Some(*n))).or_default()
+                        .set(chunk.ref_simple, chunk.data.unwrap_or(&[]));
+                },
+                /* Examining file triggers */
+                [2, 6, n] => {
+                    file_triggers.entry(*n).or_default().set(chunk.ref_simple, chunk.data.unwrap_or(&[]));
+                },
+                /* Examining layout objects, nested inside each other */
+                [4, 1, 7, x, 3, rest @ ..] => {
+                    let Some((object, entry)) = split_object_path(rest) else { continue };
+                    let data = chunk.data.unwrap_or(&[]);
+                    match entry {
+                        ObjectEntry::Trigger(n) => {
+                            layout_triggers.entry((*x, object, Some(n))).or_default().set(chunk.ref_simple, data);
+                            continue;
+                        },
+                        ObjectEntry::Action => {
+                            layout_triggers.entry((*x, object, None)).or_default().set(chunk.ref_simple, data);
+                            continue;
+                        },
+                        ObjectEntry::Object => {},
+                    }
+                    let id = object[object.len() - 1];
+                    let handle = layout_objects.entry(*x).or_default()
+                        .entry(object)
+                        .or_insert_with(|| LayoutObject { id, ..LayoutObject::default() });
                     match chunk.ref_simple {
-                        Some(16) => {
-                            if fmp_file.layouts.contains_key(&x.parse().unwrap()) {
-                                fmp_file.layouts.get_mut(&x.parse().unwrap()).unwrap().layout_name = s;
-                            } else {
-                                let tmp = component::FMComponentLayout {
-                                    layout_name: s,
-                                    created_by_account: String::new(),
-                                    create_by_user: String::new(),
-                                };
-                                fmp_file.layouts.insert(x.parse().unwrap(), tmp);
-                            }
+                        Some(metadata_constants::LAYOUT_SWITCHES) => decode_object_switches(handle, data),
+                        Some(metadata_constants::COMPONENT_NAME) => handle.name = fm_string_decrypt(data),
+                        Some(metadata_constants::OBJECT_FIELD) => {
+                            handle.field = Some(FieldTarget {
+                                table_occurrence: data.get(0..2).map_or(0, get_int) as u16,
+                                field: data.get(2..4).map_or(0, get_int) as u16,
+                                field_name: String::new(),
+                            });
                         },
-                        _ => {
-
-                        }
+                        Some(metadata_constants::OBJECT_TEXT) => handle.text = Some(fm_string_decrypt(data)),
+                        Some(metadata_constants::OBJECT_TABLE_OCCURRENCE) => {
+                            handle.table_occurrence = data.get(0..2).map(|id| get_int(id) as u16);
+                        },
+                        _ => {},
                     }
                 },
-                /* Examining field definitions for tables */
-                [x, "3", "5", y] => {
-                    if x.parse::<usize>().unwrap() >= 128 {
-                        if chunk.ctype == ChunkType::PathPush {
-                            if !fmp_file.tables.contains_key(&(x.parse::<usize>().unwrap() - 128)) {
-                                fmp_file.tables.insert(x.parse::<usize>().unwrap() - 128,
-                            component::FMComponentTable::new());
-                            }
-                            fmp_file.tables.get_mut(&(x.parse::<usize>().unwrap() - 128))
-                                .unwrap().fields
-                                    .insert(y.parse::<usize>().unwrap() as u16, 
-                                            component::FMComponentField::new());
-                        } else {
-                            let s = fm_string_decrypt(chunk.data.unwrap_or(&[0]));
-                            // match chunk.ref_simple.unwrap_or(0) {
-                            //     0 => {},
-                            //     2 => { println!("Data type: {:?}", match chunk.data.unwrap_or(&[0])[1] {
-                            //         1 => "Text",
-                            //         2 => "Number",
-                            //         3 => "Date",
-                            //         4 => "Time",
-                            //         5 => "Timestamp",
-                            //         6 => "Container",
-                            //         _ => "Unknown"
-                            //
-                            //     }); },
-                            //     3 => { println!("Description: {:?}", s); },
-                            //     16 => { println!("Field Name: {}", s); }
-                            //     129 => { println!("created by user: {}", s); }
-                            //     130 => { println!("created by user Account: {}", s); }
-                            //     _   => { println!("instr: {:x}. ref: {:?}, data: {:?}", chunk.code, chunk.ref_simple, chunk.data.unwrap()); }
-                            // };
-                            let tidx = x.parse::<usize>().unwrap() - 128;
-                            match chunk.ref_simple.unwrap_or(0) {
-                                metadata_constants::FIELD_TYPE => {
-                                    fmp_file.tables.get_mut(&tidx)
-                                        .unwrap().fields
-                                        .get_mut(&(y.parse::<usize>().unwrap() as u16))
-                                        .unwrap()
-                                        .field_type = s
-                                },
-                                metadata_constants::COMPONENT_DESC => {
-                                    fmp_file.tables.get_mut(&tidx)
-                                        .unwrap().fields
-                                        .get_mut(&(y.parse::<usize>().unwrap() as u16))
-                                        .unwrap()
-                                        .field_description = s
-                                },
-                                metadata_constants::COMPONENT_NAME => {
-                                    fmp_file.tables.get_mut(&tidx).unwrap()
-                                        .fields
-                                        .get_mut(&(y.parse::<usize>().unwrap() as u16))
-                                        .unwrap()
-                                        .field_name = s
-                                },
-                                metadata_constants::CREATOR_ACCOUNT_NAME => { 
-                                    fmp_file.tables.get_mut(&tidx).unwrap()
-                                        .fields
-                                        .get_mut(&(y.parse::<usize>().unwrap() as u16))
-                                        .unwrap()
-                                        .created_by_account = s 
-                                },
-                                metadata_constants::CREATOR_USER_NAME => {
-                                    fmp_file.tables.get_mut(&tidx).unwrap()
-                                        .fields
-                                        .get_mut(&(y.parse::<usize>().unwrap() as u16))
-                                        .unwrap()
-                                        .created_by_user = s 
-                                },
-                                _ => {},
-                            };
+                /* Examining value lists */
+                [33, 5, x] => {
+                    if chunk.ctype == ChunkType::PathPush {
+                        fmp_file.value_lists.entry(*x).or_default();
+                    } else if let Some(key) = chunk.ref_simple {
+                        let data = chunk.data.unwrap_or(&[]);
+                        let list = fmp_file.value_lists.entry(*x).or_default();
+                        match key {
+                            metadata_constants::COMPONENT_NAME => list.list_name = fm_string_decrypt(data),
+                            metadata_constants::VALUE_LIST_SWITCHES => decode_value_list_switches(list, data),
+                            metadata_constants::VALUE_LIST_CUSTOM_VALUES => {
+                                list.custom_values = decode_custom_values(&fm_string_decrypt(data));
+                            },
+                            metadata_constants::VALUE_LIST_PRIMARY_FIELD => list.primary_field = Some(decode_value_list_field(data)),
+                            metadata_constants::VALUE_LIST_SECONDARY_FIELD => list.secondary_field = Some(decode_value_list_field(data)),
+                            metadata_constants::VALUE_LIST_RELATED_FROM => {
+                                list.related_only.get_or_insert_with(Default::default).table_occurrence =
+                                    data.get(0..2).map_or(0, get_int) as u16;
+                            },
+                            metadata_constants::VALUE_LIST_DATA_SOURCE => {
+                                list.source = ValueListSource::ExternalFile {
+                                    data_source: data.get(0..2).map_or(0, get_int) as u16,
+                                    value_list: data.get(2..4).map_or(0, get_int) as u16,
+                                };
+                            },
+                            metadata_constants::CREATOR_ACCOUNT_NAME => list.created_by_account = fm_string_decrypt(data),
+                            metadata_constants::CREATOR_USER_NAME => list.create_by_user = fm_string_decrypt(data),
+                            _ => {},
                         }
                     }
-
                 },
-                /* Examining metadata for table */
-                ["3", "16", "5", x] => {
-                    let s = fm_string_decrypt(chunk.data.unwrap_or(&[0]));
+                /* Examining custom functions */
+                [16, 5, x] => {
                     if chunk.ctype == ChunkType::PathPush {
-                        if !fmp_file.tables.contains_key(&x.parse().unwrap()) {
-                            fmp_file.tables.insert(x.parse::<usize>().unwrap() - 128, component::FMComponentTable::new());
-                        } 
-                    } else {
-                        match chunk.ref_simple.unwrap_or(0) {
-                            metadata_constants::COMPONENT_NAME => { 
-                                fmp_file.tables.get_mut(&(x.parse::<usize>().unwrap() - 128)).unwrap().table_name = s },
-                            _ => {}
+                        fmp_file.custom_functions.entry(*x).or_default();
+                    } else if let Some(key) = chunk.ref_simple {
+                        let data = chunk.data.unwrap_or(&[]);
+                        let function = fmp_file.custom_functions.entry(*x).or_default();
+                        match key {
+                            metadata_constants::COMPONENT_NAME => function.name = fm_string_decrypt(data),
+                            metadata_constants::CUSTOM_FUNCTION_SWITCHES => {
+                                function.availability = Availability::from(data.first().copied().unwrap_or(0));
+                            },
+                            metadata_constants::CUSTOM_FUNCTION_PARAMETERS => {
+                                function.parameters = decode_parameters(&fm_string_decrypt(data));
+                            },
+                            metadata_constants::CALCULATION => { custom_function_bodies.insert(*x, data.to_vec()); },
+                            metadata_constants::CREATOR_ACCOUNT_NAME => function.created_by_account = fm_string_decrypt(data),
+                            metadata_constants::CREATOR_USER_NAME => function.create_by_user = fm_string_decrypt(data),
+                            _ => {},
                         }
                     }
                 },
-                /* Examining script code */
-                ["17", "5", x, "4"] => {
-                    // println!("TOP LEVEL: Path: {:?} :: ", path); 
-                    if chunk.ctype == ChunkType::PathPush {
-                        let script = script_segments.get(&x.parse().unwrap());
-                        if script.is_none() {
-                            script_segments.insert(x.parse().unwrap(), BTreeMap::new());
+                /* Examining external data sources */
+                [32, 5, x] => {
+                    if let Some(key) = chunk.ref_simple {
+                        let data = chunk.data.unwrap_or(&[]);
+                        let source = fmp_file.data_sources.entry(*x).or_default();
+                        match key {
+                            metadata_constants::COMPONENT_NAME => source.source_name = fm_string_decrypt(data),
+                            metadata_constants::DATA_SOURCE_SWITCHES => {
+                                source.source_type = DataSourceType::from(data.first().copied().unwrap_or(0));
+                            },
+                            metadata_constants::DATA_SOURCE_PATHS => source.paths = decode_paths(&fm_string_decrypt(data)),
+                            metadata_constants::CREATOR_ACCOUNT_NAME => source.created_by_account = fm_string_decrypt(data),
+                            metadata_constants::CREATOR_USER_NAME => source.create_by_user = fm_string_decrypt(data),
+                            _ => {},
                         }
-                        continue;
-                    } else if chunk.ctype == ChunkType::DataSegment {
-                        let n = chunk.segment_idx.unwrap() as usize;
-                        script_segments.get_mut(&x.parse().unwrap())
-                            .unwrap()
-                            .insert(n, chunk.data.unwrap().to_vec());
                     }
                 },
-                ["17", "5", script, "5", step, "128", "5"] => {
-                        // println!("Path: {:?}. reference: {:?}, ref_data: {:?}, data: {:x?}", 
-                        //      &path.clone(),
-                        //      chunk.ref_simple,
-                        //      chunk.ref_data,
-                        //      chunk.data,
-                        //     );
-                    let s = fm_string_decrypt(chunk.data.unwrap_or(&[0]));
-                    match chunk.ref_simple.unwrap_or(0).to_string().as_str() {
-                        "5" => {
-                            let instrs = &mut fmp_file.scripts.get_mut(&script.parse().unwrap()).unwrap().instructions;
-
-                            // println!("Searching for {step}. instructions for script {script} == {}", instrs.len());
-                            if instrs.get(&step.parse().unwrap()).is_none() {
-                                instrs.get_mut(&step.parse().unwrap()).unwrap()
-                                    .switches.insert(step.parse().unwrap(), String::new());
-                            }
-
-                            match instrs.get(&step.parse().unwrap()).unwrap().opcode {
-                                Instruction::SetVariable => {
-                                    instrs.get_mut(&step.parse().unwrap()).unwrap().switches.push(s);
-                                },
-                                Instruction::ExitScript => {
-                                    let bytecode = decompile_calculation(chunk.data.unwrap());
-                                    instrs.get_mut(&step.parse().unwrap()).unwrap().switches.push(bytecode);
-                                },
-                                _ => {
-
-                                }
-                            }
-
-                            for i in &mut *instrs {
-                                // println!("{:?}", i);
-                            }
-
-                        },
-                        _ => {
+                /* Examining privilege sets */
+                [23, 5, x] => {
+                    if chunk.ctype == ChunkType::PathPush {
+                        fmp_file.privilege_sets.entry(*x).or_default();
+                    } else if let Some(key) = chunk.ref_simple {
+                        let data = chunk.data.unwrap_or(&[]);
+                        let set = fmp_file.privilege_sets.entry(*x).or_default();
+                        match key {
+                            metadata_constants::COMPONENT_NAME => set.set_name = fm_string_decrypt(data),
+                            metadata_constants::COMPONENT_DESC => set.set_description = fm_string_decrypt(data),
+                            metadata_constants::PRIVILEGE_SWITCHES => decode_privilege_set_switches(set, data),
+                            metadata_constants::CREATOR_ACCOUNT_NAME => set.created_by_account = fm_string_decrypt(data),
+                            metadata_constants::CREATOR_USER_NAME => set.create_by_user = fm_string_decrypt(data),
+                            _ => {},
                         }
                     }
                 },
-                ["17", "5", script, "5", step, "128"] => {
-                    let s = fm_string_decrypt(chunk.data.unwrap_or(&[0]));
-                    match chunk.ref_simple.unwrap_or(0).to_string().as_str() {
-                        "1" => {
-                            // println!("Found variable: {}", s);
-                            // let script = &mut fmp_file.scripts.get_mut(&x.parse().unwrap()).unwrap().clone();
-                            let instrs = &mut fmp_file.scripts.get_mut(&script.parse().unwrap()).unwrap().instructions;
-                            // let mut step = &mut fmp_file.scripts.get_mut(&x.parse().unwrap()).unwrap()
-                            //     .instructions.get_mut(&y.parse().unwrap());
-
-                            // println!("Searching for {step}. instructions for script {script} == {}", instrs.len());
-                            if instrs.get(&step.parse().unwrap()).is_none() {
-                                instrs.get_mut(&step.parse().unwrap()).unwrap()
-                                    .switches.insert(step.parse().unwrap(), String::new());
-                            }
-
-                            match instrs.get(&step.parse().unwrap()).unwrap().opcode {
-                                Instruction::SetVariable => {
-                                    instrs.get_mut(&step.parse().unwrap()).unwrap().switches.push(s);
-                                },
-                                _ => {
-
-                                }
-                            }
-
-                            // for i in &mut *instrs {
-                            //     println!("{:?}", i);
-                            // }
-
-                        },
-                        _ => {
-                        }
+                [23, 5, x, kind @ 1..=4, y] if chunk.ref_simple == Some(metadata_constants::PRIVILEGE_SWITCHES) => {
+                    let data = chunk.data.unwrap_or(&[]);
+                    let set = fmp_file.privilege_sets.entry(*x).or_default();
+                    let level = AccessLevel::from(data.first().copied().unwrap_or(0));
+                    match kind {
+                        1 => { set.records.insert(*y, decode_record_access(data)); },
+                        2 => { set.layouts.insert(*y, level); },
+                        3 => { set.value_lists.insert(*y, level); },
+                        _ => { set.scripts.insert(*y, level); },
                     }
                 },
-                /* Examining script data */
-                ["17", "5", script, "5", step, "129", "5"] => {
-                    let s = fm_string_decrypt(chunk.data.unwrap_or(&[0]));
-                    match chunk.ref_simple.unwrap_or(0).to_string().as_str() {
-                        "5" => {
-                        // println!("Path: {:?}. reference: {:?}, ref_data: {:?}, data: {:x?}", 
-                        //      &path.clone(),
-                        //      chunk.ref_simple,
-                        //      chunk.ref_data,
-                        //      chunk.data,
-                        //     );
-                            let calc = decompile_calculation(chunk.data.unwrap());
-                            fmp_file.scripts.get_mut(&script.parse().unwrap()).unwrap()
-                                .instructions.get_mut(&step.parse().unwrap()).unwrap().switches.push(calc);
-                        },
-                        _ => {
-
+                /* Examining extended privileges */
+                [24, 5, x] => {
+                    if let Some(key) = chunk.ref_simple {
+                        let data = chunk.data.unwrap_or(&[]);
+                        let privilege = fmp_file.extended_privileges.entry(*x).or_default();
+                        match key {
+                            metadata_constants::COMPONENT_NAME => privilege.keyword = fm_string_decrypt(data),
+                            metadata_constants::COMPONENT_DESC => privilege.description = fm_string_decrypt(data),
+                            metadata_constants::PRIVILEGE_SWITCHES => privilege.privilege_sets = decode_privilege_set_ids(data),
+                            _ => {},
                         }
                     }
-                    let instrs = &mut fmp_file.scripts.get_mut(&script.parse().unwrap()).unwrap().instructions;
-                    // for i in instrs {
-                    //     println!("{:?}", i);
-                    // }
                 },
-                ["17", "5", x, ..] => {
-                    if chunk.ctype == ChunkType::PathPop 
-                        || chunk.ctype == ChunkType::PathPush {
-                        continue;
+                /* Examining accounts. Password material is stored alongside and deliberately skipped. */
+                [25, 5, x] => {
+                    if let Some(key) = chunk.ref_simple {
+                        let data = chunk.data.unwrap_or(&[]);
+                        let account = fmp_file.accounts.entry(*x).or_default();
+                        match key {
+                            metadata_constants::COMPONENT_NAME => account.account_name = fm_string_decrypt(data),
+                            metadata_constants::PRIVILEGE_SWITCHES => decode_account_switches(account, data),
+                            metadata_constants::CREATOR_ACCOUNT_NAME => account.created_by_account = fm_string_decrypt(data),
+                            metadata_constants::CREATOR_USER_NAME => account.create_by_user = fm_string_decrypt(data),
+                            _ => {},
+                        }
                     }
-
-                    // println!("Path: {:?}. reference: {:?}, ref_data: {:?}, data: {:x?}", 
-                    //      &path.clone(),
-                    //      chunk.ref_simple,
-                    //      chunk.ref_data,
-                    //      chunk.data,
-                    //      );
-                    if chunk.segment_idx == Some(4) {
-                            let instrs = chunk.data.unwrap().chunks(28);
-                            for (i, ins) in instrs.enumerate() {
-                                if ins.len() >= 21 {
-                                let oc = &INSTRUCTIONMAP[ins[21] as usize];
-                                if oc.is_some() {
-                                    let n = get_path_int(&[ins[2], ins[3]]);
-                                    let tmp = ScriptStep {
-                                        opcode: oc.clone().unwrap(),
-                                        index: n,
-                                        switches: Vec::new(),
-                                    };
-                                    let handle = &mut fmp_file.scripts
-                                        .get_mut(&x.parse().unwrap()).unwrap().instructions;
-                                        handle.insert(n, tmp);
-                                    }
-                                }
-                            }
-                    } else {
+                },
+                /* Examining field definitions for tables */
+                [x, 3, 5, y] if *x >= 128 => {
+                    let tidx = *x - 128;
+                    if chunk.ctype == ChunkType::PathPush {
+                        fmp_file.tables.entry(tidx)
+                            .or_default()
+                            .fields
+                            .entry(*y as u16)
+                            .or_default();
+                    } else if let Some(key) = chunk.ref_simple {
                         let s = fm_string_decrypt(chunk.data.unwrap_or(&[0]));
-                        match &chunk.ref_simple {
-                            // Some(2) => {
-                            // }
-                            Some(4) => {
-                                let instrs = chunk.data.unwrap().chunks(28);
-                                for ins in instrs {
-                                    if ins.len() >= 21 {
-                                        // println!("{}, ref_data: {}", 
-                                        //         i + 1,
-                                        //      ins[21]);
-                                    let oc = &INSTRUCTIONMAP[ins[21] as usize];
-                                    if oc.is_some() {
-                                        let n = get_path_int(&[ins[2], ins[3]]);
-                                        let tmp = ScriptStep {
-                                            opcode: oc.clone().unwrap(),
-                                            index: n,
-                                            switches: Vec::new(),
-                                        };
-
-                                        println!("Adding idx: {}", tmp.index);
-                                        let handle = &mut fmp_file.scripts
-                                            .get_mut(&x.parse().unwrap()).unwrap().instructions;
-                                            handle.insert(n, tmp);
-                                        }
-                                    }
-                                }
+                        let Some(field) = fmp_file.tables.get_mut(&tidx).and_then(|t| t.fields.get_mut(&(*y as u16))) else {
+                            fmp_file.diagnostics.push(FmpError::DanglingReference { component: "field", id: *y });
+                            continue;
+                        };
+                        match key {
+                            metadata_constants::FIELD_TYPE => {
+                                field_switches.insert((tidx, *y as u16), chunk.data.unwrap_or(&[]).to_vec());
                             },
-                            None => {
-                                if chunk.ctype == ChunkType::DataSegment {
-                                    // println!("Data: {:?}. Segment: {:?}. Data: {:?}", chunk.path, chunk.segment_idx, chunk.data)
-                                } else {
-                                    // println!("Instruction: {:?}. Data: {:?}", chunk.ctype, chunk.data)
-                                }
+                            metadata_constants::FIELD_CONTEXT => {
+                                field.context_occurrence = chunk.data.and_then(|d| d.get(0..2)).map_or(0, get_int) as u16;
                             },
-                            _ => {
-                                // println!("Path: {:?}. reference: {:?}, ref_data: {:?}", 
-                                //      &path.clone(),
-                                //      chunk.ref_simple,
-                                //      chunk.data);
+                            metadata_constants::FIELD_SUMMARY => {
+                                field.summary = Some(decode_field_summary(chunk.data.unwrap_or(&[])));
                             },
-                        }
+                            metadata_constants::VALIDATION_RANGE_FROM => {
+                                field.validation.range.get_or_insert_with(Default::default).from = s;
+                            },
+                            metadata_constants::VALIDATION_RANGE_TO => {
+                                field.validation.range.get_or_insert_with(Default::default).to = s;
+                            },
+                            metadata_constants::VALIDATION_VALUE_LIST => {
+                                field.validation.value_list = chunk.data.and_then(|d| d.get(0..2)).map(|id| get_int(id) as u16);
+                            },
+                            metadata_constants::VALIDATION_MAX_CHARACTERS => {
+                                field.validation.max_characters = chunk.data.and_then(|d| d.get(0..2)).map(|n| get_int(n) as u16);
+                            },
+                            metadata_constants::VALIDATION_MESSAGE => field.validation.error_message = Some(s),
+                            metadata_constants::AUTO_ENTER_DATA => field.auto_enter.data = Some(s),
+                            metadata_constants::AUTO_ENTER_SERIAL_NEXT => {
+                                field.auto_enter.serial.get_or_insert_with(Default::default).next_value = s;
+                            },
+                            metadata_constants::AUTO_ENTER_SERIAL_INCREMENT => {
+                                field.auto_enter.serial.get_or_insert_with(Default::default).increment = s;
+                            },
+                            metadata_constants::AUTO_ENTER_LOOKUP => {
+                                let data = chunk.data.unwrap_or(&[]);
+                                let lookup = field.auto_enter.lookup.get_or_insert_with(Default::default);
+                                lookup.table_occurrence = data.get(0..2).map_or(0, get_int) as u16;
+                                lookup.field = data.get(2..4).map_or(0, get_int) as u16;
+                            },
+                            metadata_constants::COMPONENT_DESC => field.field_description = s,
+                            metadata_constants::COMPONENT_NAME => field.field_name = s,
+                            metadata_constants::CREATOR_ACCOUNT_NAME => field.created_by_account = s,
+                            metadata_constants::CREATOR_USER_NAME => field.created_by_user = s,
+                            _ => {},
+                        };
                     }
-
                 },
-                /* Examining script metadata */
-                ["17", "1", x, ..] => {
-                    if chunk.ctype == ChunkType::PathPush 
-                        || chunk.ctype == ChunkType::PathPop {
-                        continue;
+                /* Examining calculation field formulas, auto-enter and validation calculations */
+                [x, 3, 5, y, slot @ 5..=7] if *x >= 128 => {
+                    if let (Some(metadata_constants::CALCULATION), Some(data)) = (chunk.ref_simple, chunk.data) {
+                        field_calculations.push(FieldCalculation {
+                            table: *x - 128,
+                            field: *y as u16,
+                            slot: *slot,
+                            data: data.to_vec(),
+                        });
                     }
-                    
-                    if chunk.ctype == ChunkType::RefSimple {
-                        match chunk.ref_simple {
-                            Some(16) => {
-                                let handle = fmp_file.scripts.get_mut(&x.parse().unwrap());
-                                if handle.is_none() {
-                                    let tmp = component::FMComponentScript {
-                                        script_name: fm_string_decrypt(chunk.data.unwrap()),
-                                        instructions: HashMap::new(),
-                                        create_by_user: String::new(),
-                                        arguments: Vec::new(),
-                                        created_by_account: String::new(),
-                                    };
-                                    fmp_file.scripts.insert(path.last().unwrap().parse().unwrap(), tmp);
-                                } else {
-                                    handle.unwrap().script_name = fm_string_decrypt(chunk.data.unwrap_or(&[0]));
-                                }
-                            },
-                            _ => {
-                                // println!("{}", fm_string_decrypt(chunk.data.unwrap()));
-                            }
+                },
+                /* Examining metadata for table */
+                [3, 16, 5, x] if *x >= 128 => {
+                    let tidx = *x - 128;
+                    if chunk.ctype == ChunkType::PathPush {
+                        fmp_file.tables.entry(tidx).or_default();
+                    } else if chunk.ref_simple == Some(metadata_constants::COMPONENT_NAME) {
+                        match fmp_file.tables.get_mut(&tidx) {
+                            Some(table) => table.table_name = fm_string_decrypt(chunk.data.unwrap_or(&[0])),
+                            None => fmp_file.diagnostics.push(FmpError::DanglingReference { component: "table", id: tidx }),
+                        }
+                    }
+                },
+                /* Examining script code */
+                [17, 5, x, 4] => {
+                    let segments = script_segments.entry(*x).or_default();
+                    if chunk.ctype == ChunkType::DataSegment {
+                        if let (Some(n), Some(data)) = (chunk.segment_idx, chunk.data) {
+                            segments.insert(n as usize, data.to_vec());
                         }
                     }
-                    // if chunk.ref_simple == Some(16) {
-                        // println!("Path: {:?}. reference: {:?}, ref_data: {:?}", 
-                        //      &path.clone(),
-                        //      chunk.ref_simple,
-                        //      s);
-                    // } else {
-                        // println!("Path: {:?}. reference: {:?}, data: {:?}, ref_data: {:?}", &path.clone(),
-                        //      chunk.ref_simple,
-                        //      chunk.data,
-                        //      chunk.ref_data);
-                    // }
                 },
-                _ => { 
+                /* Examining script step data */
+                [17, 5, script, 5, step, rest @ ..] => {
+                    if let (Some(key), Some(data)) = (chunk.ref_simple, chunk.data) {
+                        step_data.push(StepData {
+                            script: *script,
+                            step: *step,
+                            path: rest.to_vec(),
+                            key,
+                            data: data.to_vec(),
+                        });
+                    }
+                },
+                [17, 5, x] if chunk.segment_idx == Some(4) || chunk.ref_simple == Some(4) => {
+                    let handle = &mut fmp_file.scripts
+                        .entry(*x)
+                        .or_default()
+                        .instructions;
+                    for step in decode_steps(chunk.data.unwrap_or(&[])) {
+                        handle.insert(handle.len(), step);
+                    }
+                },
+                /* Examining the script catalog: scripts, folders and separators */
+                [17, 1, .., x] if chunk.ctype == ChunkType::RefSimple => {
+                    let data = chunk.data.unwrap_or(&[]);
+                    match chunk.ref_simple {
+                        Some(metadata_constants::COMPONENT_NAME) => {
+                            script_catalog.entry(*x).or_default().name = fm_string_decrypt(data);
+                        },
+                        Some(metadata_constants::CATALOG_SWITCHES) => {
+                            decode_catalog_switches(script_catalog.entry(*x).or_default(), data);
+                        },
+                        _ => {},
+                    }
+                },
+                _ => {
                 }
             }
         }
 
-
-        idx = sectors[idx].next;
+        idx = current.next;
     }
     /* Assemble scripts */
-    for (script, segments) in &mut script_segments {
-        let mut instructions = Vec::<u8>::new();
-        for s in segments {
-            instructions.append(s.1);
+    for (script, segments) in script_segments {
+        let instructions = segments.into_values().flatten().collect::<Vec<u8>>();
+        let handle = &mut fmp_file.scripts
+            .entry(script)
+            .or_default()
+            .instructions;
+        for step in decode_steps(&instructions) {
+            handle.insert(handle.len(), step);
+        }
+    }
+
+    for (id, entry) in script_catalog.iter().filter(|(_, e)| e.kind == CatalogKind::Script) {
+        let script = fmp_file.scripts.entry(*id).or_default();
+        script.script_name = entry.name.clone();
+        script.folder_path = folder_path(&script_catalog, *id);
+        script.include_in_menu = entry.include_in_menu;
+        script.full_access = entry.full_access;
+    }
+    /* Scripts missing from the catalog keep their own details and are listed after everything else */
+    for (id, script) in &fmp_file.scripts {
+        script_catalog.entry(*id).or_insert_with(|| CatalogEntry {
+            name: script.script_name.clone(),
+            position: usize::MAX,
+            ..CatalogEntry::default()
+        });
+    }
+    fmp_file.script_tree = build_script_tree(&script_catalog);
+
+    /* Resolve every trigger and button action, keeping a copy with the layout or object it belongs to */
+    let resolve_trigger = |data: &TriggerData, source: TriggerSource, fmp_file: &FmpFile| {
+        let mut trigger = decode_trigger(&data.switches, source);
+        trigger.script_name = fmp_file.scripts.get(&trigger.script_id)
+            .map(|s| s.script_name.clone())
+            .unwrap_or_default();
+        trigger.parameter_calc = (!data.parameter.is_empty()).then(|| decode_resolved(&data.parameter, fmp_file));
+        trigger
+    };
+    for data in file_triggers.values() {
+        let trigger = resolve_trigger(data, TriggerSource::File, &fmp_file);
+        fmp_file.triggers.push(trigger);
+    }
+    for ((layout, object, n), data) in layout_triggers {
+        if object.is_empty() {
+            let trigger = resolve_trigger(&data, TriggerSource::Layout { layout }, &fmp_file);
+            fmp_file.layouts.entry(layout).or_default().triggers.push(trigger.clone());
+            fmp_file.triggers.push(trigger);
+            continue;
+        }
+        let mut trigger = resolve_trigger(&data, TriggerSource::LayoutObject { layout, object: object.clone() }, &fmp_file);
+        if n.is_none() {
+            trigger.event = TriggerEvent::ButtonAction;
+        }
+        if let Some(handle) = layout_objects.get_mut(&layout).and_then(|o| o.get_mut(&object)) {
+            handle.triggers.push(trigger.clone());
+        }
+        fmp_file.triggers.push(trigger);
+    }
+    for (layout, objects) in layout_objects {
+        let mut objects = build_object_tree(objects);
+        for object in objects.iter_mut() {
+            object.walk_mut(&mut |o| {
+                if let Some(field) = o.field.as_mut() {
+                    field.field_name = fmp_file.field_reference_name(field.table_occurrence as usize, field.field as usize)
+                        .unwrap_or_default();
+                }
+                if let Some(occurrence) = o.table_occurrence.and_then(|id| fmp_file.table_occurrences.get(&(id as usize))) {
+                    o.table_occurrence_name = occurrence.table_occurence_name.clone();
+                }
+            });
+        }
+        fmp_file.layouts.entry(layout).or_default().objects = objects;
+    }
+    for (layout, parts) in layout_parts {
+        fmp_file.layouts.entry(layout).or_default().parts = parts.into_values().collect();
+    }
+    let occurrence_names = fmp_file.layouts.values()
+        .map(|l| fmp_file.table_occurrences.get(&(l.table_occurrence as usize))
+            .map(|o| o.table_occurence_name.clone())
+            .unwrap_or_default())
+        .collect::<Vec<_>>();
+    for (layout, name) in fmp_file.layouts.values_mut().zip(occurrence_names) {
+        layout.table_occurrence_name = name;
+    }
+
+    /* Name the base table of each occurrence, or the data source it comes from */
+    for occurrence in fmp_file.table_occurrences.values_mut() {
+        match occurrence.base_table() {
+            BaseTable::Local(table) => if let Some(table) = fmp_file.tables.get(&(table as usize)) {
+                occurrence.table_actual_name = table.table_name.clone();
+            },
+            BaseTable::External { data_source, .. } => if let Some(source) = fmp_file.data_sources.get(&(data_source as usize)) {
+                occurrence.data_source_name = source.source_name.clone();
+            },
+        }
+    }
+
+    /* Name both sides of each relationship, and the fields joined or sorted on */
+    let occurrence_name = |id: u16| fmp_file.table_occurrences.get(&(id as usize))
+        .map(|o| o.table_occurence_name.clone())
+        .unwrap_or_default();
+    let field_name = |occurrence: u16, field: u16| fmp_file.field_reference_name(occurrence as usize, field as usize)
+        .unwrap_or_default();
+    let relationships = fmp_file.relationships.iter()
+        .map(|(id, r)| {
+            let mut r = r.clone();
+            if let Some(table1) = r.table1 {
+                r.table1_name = occurrence_name(table1);
+                for p in r.predicates.iter_mut() {
+                    p.field1_name = field_name(table1, p.field1);
+                }
+                for s in r.table1_options.sort.iter_mut() {
+                    s.field_name = field_name(table1, s.field);
+                }
+            }
+            r.table2_name = occurrence_name(r.table2);
+            for p in r.predicates.iter_mut() {
+                p.field2_name = field_name(r.table2, p.field2);
+            }
+            for s in r.table2_options.sort.iter_mut() {
+                s.field_name = field_name(r.table2, s.field);
+            }
+            (*id, r)
+        })
+        .collect::<Vec<_>>();
+
+    /* Name the fields and occurrences value lists draw from */
+    let value_lists = fmp_file.value_lists.iter()
+        .map(|(id, list)| {
+            let mut list = list.clone();
+            for field in list.primary_field.iter_mut().chain(list.secondary_field.iter_mut()) {
+                field.field_name = field_name(field.table_occurrence, field.field);
+            }
+            if let Some(related) = list.related_only.as_mut() {
+                related.table_occurrence_name = occurrence_name(related.table_occurrence);
+            }
+            (*id, list)
+        })
+        .collect::<Vec<_>>();
+    fmp_file.relationships.extend(relationships);
+    fmp_file.value_lists.extend(value_lists);
+
+    /* Name each account's privilege set and list the extended privileges each set holds */
+    for account in fmp_file.accounts.values_mut() {
+        if let Some(set) = fmp_file.privilege_sets.get(&account.privilege_set) {
+            account.privilege_set_name = set.set_name.clone();
+        }
+    }
+    let mut extended_privileges = fmp_file.extended_privileges.iter().collect::<Vec<_>>();
+    extended_privileges.sort_by_key(|(id, _)| **id);
+    for (_, privilege) in extended_privileges {
+        for id in &privilege.privilege_sets {
+            if let Some(set) = fmp_file.privilege_sets.get_mut(id) {
+                set.extended_privileges.push(privilege.keyword.clone());
+            }
         }
+    }
 
-        for instr in instructions.chunks(28) {
-            if instr.len() < 28 {
-                continue;
+    /* Field calculations, then the switches that decide which of a field's options are on */
+    for c in field_calculations {
+        let (calculation, error) = match decode_resolved(&c.data, &fmp_file) {
+            Ok(calc) => (Some(calc), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let Some(field) = fmp_file.tables.get_mut(&c.table).and_then(|t| t.fields.get_mut(&c.field)) else {
+            fmp_file.diagnostics.push(FmpError::DanglingReference { component: "field", id: c.field as usize });
+            continue;
+        };
+        match c.slot {
+            5 => (field.calculation, field.calculation_error) = (calculation, error),
+            6 => (field.auto_enter.calculation, field.auto_enter.calculation_error) = (calculation, error),
+            _ => (field.validation.calculation, field.validation.calculation_error) = (calculation, error),
+        }
+    }
+    for ((table, field), switches) in field_switches {
+        if let Some(field) = fmp_file.tables.get_mut(&table).and_then(|t| t.fields.get_mut(&field)) {
+            decode_field_switches(field, &switches);
+        }
+    }
+
+    /* Name the table occurrences and fields that field definitions point at */
+    let lookup_sources = fmp_file.tables.iter()
+        .flat_map(|(table, t)| t.fields.iter().map(move |(field, f)| (*table, *field, f)))
+        .filter_map(|(table, field, f)| {
+            let lookup = f.auto_enter.lookup.as_ref()?;
+            let name = fmp_file.field_reference_name(lookup.table_occurrence as usize, lookup.field as usize)?;
+            Some((table, field, name))
+        })
+        .collect::<Vec<_>>();
+    for (table, field, name) in lookup_sources {
+        if let Some(lookup) = fmp_file.tables.get_mut(&table)
+            .and_then(|t| t.fields.get_mut(&field))
+            .and_then(|f| f.auto_enter.lookup.as_mut()) {
+            lookup.source_name = name;
+        }
+    }
+    for table in fmp_file.tables.values_mut() {
+        let names = table.fields.iter()
+            .map(|(id, field)| (*id, field.field_name.clone()))
+            .collect::<HashMap<_, _>>();
+        for field in table.fields.values_mut() {
+            if let Some(occurrence) = fmp_file.table_occurrences.get(&(field.context_occurrence as usize)) {
+                field.context_occurrence_name = occurrence.table_occurence_name.clone();
             }
-            let op = &INSTRUCTIONMAP[instr[21] as usize];
-            if op.is_some() {
-                let tmp = ScriptStep {
-                    opcode: op.clone().unwrap(),
-                    index: get_path_int(&[instr[2], instr[3]]),
-                    switches: vec![],
-                };
-                let handle = fmp_file.scripts.get_mut(&script).unwrap();
-                handle.instructions.insert(handle.instructions.len(), tmp);
+            if let Some(summary) = field.summary.as_mut() {
+                summary.field_name = names.get(&summary.field).cloned().unwrap_or_default();
             }
+
+        }
+    }
+
+    /* Custom function bodies may call any custom function, themselves included */
+    for (id, bytecode) in custom_function_bodies {
+        let body = decode_resolved(&bytecode, &fmp_file);
+        let function = fmp_file.custom_functions.entry(id).or_default();
+        match body {
+            Ok(body) => function.body = Some(body),
+            Err(e) => function.body_error = Some(e.to_string()),
         }
     }
-    return fmp_file;
+
+    /* Decode step options now that every step is known, once fields, layouts and scripts can be named */
+    let mut options = BTreeMap::<(usize, usize), StepOptions>::new();
+    for d in step_data {
+        options.entry((d.script, d.step)).or_default().insert((d.path, d.key), d.data);
+    }
+    for ((script, index), options) in options {
+        /* Steps with an opcode missing from INSTRUCTIONMAP were never decoded, their options are skipped. */
+        let Some(script_steps) = fmp_file.scripts.get(&script) else {
+            fmp_file.diagnostics.push(FmpError::DanglingReference { component: "script", id: script });
+            continue;
+        };
+        let Some(position) = script_steps
+            .instructions
+            .iter()
+            .find(|(_, s)| s.index == index)
+            .map(|(position, _)| *position) else { continue };
+        let params = decode_step_params(&fmp_file.scripts[&script].instructions[&position].opcode, &options, &fmp_file);
+        if let Some(step) = fmp_file.scripts.get_mut(&script).and_then(|s| s.instructions.get_mut(&position)) {
+            step.params = params;
+        }
+    }
+    Ok(fmp_file)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use crate::decompile::decompiler::*;
+    use crate::fmp_format::sector::SECTOR_SIZE;
+    use crate::repr::script_tree::ScriptTreeItem;
+    use crate::util::test_util::{encrypt, single_sector_file};
+
+    #[test]
+    fn decompile_from_bytes_and_reader() {
+        /* [17].[1].[5]::16 = "hello" */
+        let bytes = single_sector_file(&[0x20, 0x11, 0x20, 0x01, 0x20, 0x05,
+            0x06, 0x10, 0x05, 0x32, 0x3f, 0x36, 0x36, 0x35, 0x40, 0x40, 0x40]);
+        let from_bytes = decompile_fmp12_bytes(&bytes).unwrap();
+        assert_eq!(from_bytes.scripts[&5].script_name, "hello");
+        assert_eq!(from_bytes.header.application_version, "Pro 19.0");
+        let from_reader = decompile_fmp12_reader(Cursor::new(bytes)).unwrap();
+        assert_eq!(from_reader.scripts[&5].script_name, "hello");
+    }
+
+    #[test]
+    fn decompile_file_header() {
+        let path = std::env::temp_dir().join(format!("fmplib_header_{}.fmp12", std::process::id()));
+        std::fs::write(&path, single_sector_file(&[])).unwrap();
+        let file = decompile_fmp12_file(&path).unwrap();
+        assert_eq!(file.header.application_version, "Pro 19.0");
+
+        std::fs::write(&path, [0u8; SECTOR_SIZE]).unwrap();
+        assert!(matches!(decompile_fmp12_file(&path), Err(FmpError::BadHeader(_))));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn decompile_unknown_steps() {
+        /* Script 1 holds an unmapped opcode 2 at step 129 and a comment at step 130,
+         * each with text at [17].[5].[1].[5].[step].[128]::1 */
+        let mut code = [0u8; 56];
+        code[1..4].copy_from_slice(&[1, 0, 1]);
+        code[21] = 2;
+        code[29..32].copy_from_slice(&[1, 0, 2]);
+        code[49] = 89;
+        let mut payload = vec![0x20, 0x11, 0x20, 0x05, 0x20, 0x01, 0x06, 0x04, 56];
+        payload.extend(code);
+        payload.extend([0x20, 0x05, 0x20, 0x81, 0x20, 0x80, 0x06, 0x01, 0x01, 0x22, 0x40, 0x40,
+            0x20, 0x82, 0x20, 0x80, 0x06, 0x01, 0x05, 0x32, 0x3f, 0x36, 0x36, 0x35, 0x40, 0x40,
+            0x40, 0x40, 0x40, 0x40]);
+        let file = decompile_fmp12_bytes(&single_sector_file(&payload)).unwrap();
+        let steps = &file.scripts[&1].instructions;
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[&0].params, StepParams::Comment { text: "hello".to_string() });
+    }
+
+    #[test]
+    fn decompile_field_defaults() {
+        /* [129].[3].[5].[1]::16 = "hello", with no field type switches */
+        let bytes = single_sector_file(&[0x20, 0x81, 0x20, 0x03, 0x20, 0x05, 0x20, 0x01,
+            0x06, 0x10, 0x05, 0x32, 0x3f, 0x36, 0x36, 0x35, 0x40, 0x40, 0x40, 0x40]);
+        let file = decompile_fmp12_bytes(&bytes).unwrap();
+        let field = &file.tables[&1].fields[&1];
+        assert_eq!(field.field_name, "hello");
+        assert_eq!(field.repetitions, 1);
+    }
+
+    #[test]
+    fn decompile_field_calculations() {
+        /* Field 1 of table 1 is a calculation with a validation calculation that fails to decode */
+        let mut switches = vec![0x06, 0x02, 16, 1, 2];
+        switches.extend([0; 14]);
+        switches[18] = 0x01;
+        let payload = [
+            vec![0x20, 0x81, 0x20, 0x03, 0x20, 0x05, 0x20, 0x01, 0x06, 0x10, 0x05], encrypt("hello"), switches,
+            vec![0x20, 0x05, 0x06, 0x05, 0x05, 0x16, 0, 4, 0, 1, 0x40],
+            vec![0x20, 0x07, 0x06, 0x05, 0x01, 0x04, 0x40, 0x40, 0x40, 0x40, 0x40],
+        ].concat();
+        let file = decompile_fmp12_bytes(&single_sector_file(&payload)).unwrap();
+        let field = &file.tables[&1].fields[&1];
+        assert_eq!(field.calculation.as_ref().map(|c| c.to_string()).as_deref(), Some("<unresolved 4:1>"));
+        assert_eq!(field.calculation_error, None);
+        assert_eq!(field.validation.calculation, None);
+        assert_eq!(field.validation.calculation_error, Some(CalcError::UnexpectedEnd.to_string()));
+    }
+
+    #[test]
+    fn decompile_dangling_references() {
+        /* A table occurrence name at [3].[17].[5].[0].[128]::16 with no occurrence defined before it,
+         * then [17].[1].[5]::16 = "hello" */
+        let payload = [
+            vec![0x20, 0x03, 0x20, 0x11, 0x20, 0x05, 0x20, 0x00, 0x20, 0x80, 0x06, 0x10, 0x02], encrypt("TO"),
+            vec![0x40, 0x40, 0x40, 0x40, 0x40],
+            vec![0x20, 0x11, 0x20, 0x01, 0x20, 0x05, 0x06, 0x10, 0x05], encrypt("hello"), vec![0x40, 0x40, 0x40],
+        ].concat();
+        let file = decompile_fmp12_bytes(&single_sector_file(&payload)).unwrap();
+        assert!(file.table_occurrences.is_empty());
+        assert!(matches!(file.diagnostics[..], [FmpError::DanglingReference { component: "table occurrence", id: 0 }]));
+        assert_eq!(file.scripts[&5].script_name, "hello");
+    }
+
+    #[test]
+    fn decompile_relationships() {
+        /* Below [3].[17].[5].[0]: a short relationship to occurrence 2 before any occurrence,
+         * occurrence 1, a short relationship after it and a full one from occurrence 7 */
+        let mut occurrence = vec![0x20, 0x80, 0x06, 0x02, 10];
+        occurrence.extend([0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0x40]);
+        let payload = [
+            vec![0x20, 0x03, 0x20, 0x11, 0x20, 0x05, 0x20, 0x00],
+            vec![0x20, 0xfb, 0x10, 0, 0, 2, 0x40],
+            occurrence,
+            vec![0x20, 0xfb, 0x10, 0, 0, 2, 0x40],
+            vec![0x20, 0xfb, 0x12, 0, 0, 2, 0, 7, 0x40],
+            vec![0x40, 0x40, 0x40, 0x40],
+        ].concat();
+        let file = decompile_fmp12_bytes(&single_sector_file(&payload)).unwrap();
+        let sides = (0..3)
+            .map(|id| (file.relationships[&id].table1, file.relationships[&id].table2))
+            .collect::<Vec<_>>();
+        assert_eq!(sides, [(None, 2), (Some(1), 2), (Some(7), 2)]);
+    }
+
+    #[test]
+    fn decompile_uncatalogued_scripts() {
+        /* [17].[1].[5]::16 = "hello", and steps for script 9 that has no catalog entry */
+        let mut code = [0u8; 28];
+        code[1..4].copy_from_slice(&[1, 0, 1]);
+        code[21] = 89;
+        let payload = [
+            vec![0x20, 0x11, 0x20, 0x01, 0x20, 0x05, 0x06, 0x10, 0x05], encrypt("hello"), vec![0x40, 0x40, 0x40],
+            vec![0x20, 0x11, 0x20, 0x05, 0x20, 0x09, 0x06, 0x04, 28], code.to_vec(), vec![0x40, 0x40, 0x40],
+        ].concat();
+        let file = decompile_fmp12_bytes(&single_sector_file(&payload)).unwrap();
+        assert_eq!(file.scripts[&5].script_name, "hello");
+        assert_eq!(file.scripts[&9].instructions.len(), 1);
+        assert_eq!(file.script_tree, [
+            ScriptTreeItem::Script { id: 5, name: "hello".to_string() },
+            ScriptTreeItem::Script { id: 9, name: String::new() },
+        ]);
+    }
+
+    #[test]
+    fn decompile_file_triggers() {
+        /* [2].[6].[1]::2 = OnFirstWindowOpen running script 5, [17].[1].[5]::16 = "hello" */
+        let bytes = single_sector_file(&[0x20, 0x02, 0x20, 0x06, 0x20, 0x01,
+            0x06, 0x02, 0x03, 20, 0, 5, 0x40, 0x40, 0x40,
+            0x20, 0x11, 0x20, 0x01, 0x20, 0x05,
+            0x06, 0x10, 0x05, 0x32, 0x3f, 0x36, 0x36, 0x35, 0x40, 0x40, 0x40]);
+        let file = decompile_fmp12_bytes(&bytes).unwrap();
+        assert_eq!(file.triggers.len(), 1);
+        assert_eq!(file.triggers[0].event, TriggerEvent::OnFirstWindowOpen);
+        assert_eq!(file.triggers[0].source, TriggerSource::File);
+        assert_eq!((file.triggers[0].script_id, file.triggers[0].script_name.as_str()), (5, "hello"));
+        assert_eq!(file.triggers[0].parameter_calc, None);
+    }
+
+    #[test]
+    fn decompile_custom_functions() {
+        /* [16].[5].[2]::16 = "Fact", ::4 = "n", ::5 = Fact ( n ) */
+        let bytes = single_sector_file(&[0x20, 0x10, 0x20, 0x05, 0x20, 0x02,
+            0x06, 0x10, 0x04, 0x1c, 0x3b, 0x39, 0x2e, 0x06, 0x04, 0x01, 0x34,
+            0x06, 0x05, 0x08, 0x17, 0, 2, 0x04, 0x1a, 0x01, 0x34, 0x05, 0x40, 0x40, 0x40]);
+        let file = decompile_fmp12_bytes(&bytes).unwrap();
+        let function = &file.custom_functions[&2];
+        assert_eq!(function.parameters, ["n"]);
+        assert_eq!(function.body_error, None);
+        assert_eq!(function.body.as_ref().unwrap().to_string(), "Fact ( n )");
+        assert!(function.is_recursive(2));
+    }
+
+    #[test]
+    fn decompile_data_sources() {
+        /* [32].[5].[1]::16 = "CRM", ::4 = "file:CRM",
+         * [3].[17].[5].[0] occurrence "Customers" of table 1 in data source 1 */
+        let bytes = single_sector_file(&[0x20, 0x20, 0x20, 0x05, 0x20, 0x01,
+            0x06, 0x10, 0x03, 0x19, 0x08, 0x17,
+            0x06, 0x04, 0x08, 0x3c, 0x33, 0x36, 0x3f, 0x60, 0x19, 0x08, 0x17, 0x40, 0x40, 0x40,
+            0x20, 0x03, 0x20, 0x11, 0x20, 0x05, 0x20, 0x00,
+            0x06, 0x02, 0x0a, 0, 0, 0, 0, 0, 0, 1, 0, 0, 1,
+            0x06, 0x10, 0x09, 0x19, 0x2f, 0x29, 0x2e, 0x35, 0x37, 0x3f, 0x28, 0x29, 0x40, 0x40, 0x40, 0x40]);
+        let file = decompile_fmp12_bytes(&bytes).unwrap();
+        let source = &file.data_sources[&1];
+        assert_eq!((source.source_name.as_str(), source.source_type), ("CRM", DataSourceType::FileMaker));
+        assert_eq!(source.paths[0].file_name(), "CRM");
+        let occurrence = &file.table_occurrences[&1];
+        assert_eq!(occurrence.base_table(), BaseTable::External { data_source: 1, table: 1 });
+        assert_eq!(occurrence.data_source_name, "CRM");
+    }
+
+    #[test]
+    fn decompile_security() {
+        /* [23].[5].[1]::16 = "Full", [24].[5].[1]::16 = "fmapp" enabled for set 1,
+         * [25].[5].[2]::16 = "Admin", an active FileMaker account with set 1 */
+        let bytes = single_sector_file(&[0x20, 0x17, 0x20, 0x05, 0x20, 0x01,
+            0x06, 0x10, 0x04, 0x1c, 0x2f, 0x36, 0x36, 0x40, 0x40, 0x40,
+            0x20, 0x18, 0x20, 0x05, 0x20, 0x01,
+            0x06, 0x10, 0x05, 0x3c, 0x37, 0x3b, 0x2a, 0x2a, 0x06, 0x02, 0x02, 0, 1, 0x40, 0x40, 0x40,
+            0x20, 0x19, 0x20, 0x05, 0x20, 0x02,
+            0x06, 0x10, 0x05, 0x1b, 0x3e, 0x37, 0x33, 0x34, 0x06, 0x02, 0x04, 0, 1, 0, 1, 0x40, 0x40, 0x40]);
+        let file = decompile_fmp12_bytes(&bytes).unwrap();
+        let account = &file.accounts[&2];
+        assert_eq!((account.account_name.as_str(), account.active), ("Admin", true));
+        assert_eq!(account.privilege_set_name, "Full");
+        assert_eq!(file.privilege_sets[&1].extended_privileges, ["fmapp"]);
+    }
+
+    #[test]
+    fn decompile_short_input() {
+        assert!(matches!(decompile_fmp12_bytes(&[0; 100]), Err(FmpError::BadHeader(_))));
+        assert!(matches!(decompile_fmp12_bytes(&[0; SECTOR_SIZE * 2]), Err(FmpError::BadHeader(_))));
+        let mut bytes = single_sector_file(&[]);
+        bytes.truncate(SECTOR_SIZE * 2 + 10);
+        assert!(matches!(decompile_fmp12_bytes(&bytes),
+            Err(FmpError::TruncatedSector { sector: 2, offset: 10 })));
+    }
 }
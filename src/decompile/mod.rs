@@ -1 +1,2 @@
 pub mod decompiler;
+pub mod steps;
@@ -0,0 +1,252 @@
+use std::collections::BTreeMap;
+
+use crate::calc_engine::calc::FieldRef;
+use crate::decompile::decompiler::{decode_field_reference, decode_resolved};
+use crate::repr::file::FmpFile;
+use crate::script_engine::instructions::Instruction;
+use crate::script_engine::step_params::{Animation, LayoutTarget, StepCalc, StepParams};
+use crate::util::format_decode::{fm_string_decrypt, get_int};
+
+/// Data found below one step's directory, keyed by the path below the step and the key.
+pub(crate) type StepOptions = BTreeMap<(Vec<usize>, u16), Vec<u8>>;
+
+/* Options live in directories numbered from 128, text at key 1, ids at key 2
+ * and calculations at [option].[5]::5. */
+fn text(options: &StepOptions, option: usize) -> Option<String> {
+    options.get(&(vec![option], 1)).map(|data| fm_string_decrypt(data))
+}
+
+fn calc(options: &StepOptions, option: usize, file: &FmpFile) -> Option<StepCalc> {
+    options.get(&(vec![option, 5], 5))
+        .filter(|data| !data.is_empty())
+        .map(|data| decode_resolved(data, file))
+}
+
+fn ids(options: &StepOptions, option: usize) -> Option<&[u8]> {
+    options.get(&(vec![option], 2)).map(|data| data.as_slice())
+}
+
+fn field(options: &StepOptions, option: usize, file: &FmpFile) -> Option<FieldRef> {
+    ids(options, option).map(|data| decode_field_reference(data, file))
+}
+
+fn go_to_layout(options: &StepOptions, file: &FmpFile) -> StepParams {
+    let switches = ids(options, 128).unwrap_or(&[]);
+    let calculation = || calc(options, 128, file);
+    let target = match switches.first() {
+        Some(1) => {
+            let id = switches.get(1..3).map_or(0, get_int);
+            LayoutTarget::Layout {
+                id,
+                name: file.layouts.get(&id).map(|l| l.layout_name.clone()).unwrap_or_default(),
+            }
+        },
+        Some(2) => LayoutTarget::ByNameCalc(calculation()),
+        Some(3) => LayoutTarget::ByNumberCalc(calculation()),
+        _ => LayoutTarget::Original,
+    };
+    let animation = ids(options, 129)
+        .and_then(|data| data.first())
+        .map_or(Animation::None, |byte| Animation::from(*byte));
+    StepParams::GoToLayout { target, animation }
+}
+
+fn perform_script(options: &StepOptions, file: &FmpFile) -> StepParams {
+    let switches = ids(options, 128).unwrap_or(&[]);
+    let script = switches.get(0..2).map_or(0, get_int);
+    let from_file = switches.get(2..4).map(get_int).filter(|source| *source != 0);
+    StepParams::PerformScript {
+        script,
+        /* Scripts in another file can only be named once that file is loaded. */
+        script_name: match from_file {
+            None => file.scripts.get(&script).map(|s| s.script_name.clone()).unwrap_or_default(),
+            Some(_) => String::new(),
+        },
+        from_file,
+        parameter: calc(options, 129, file),
+    }
+}
+
+fn show_custom_dialog(options: &StepOptions, file: &FmpFile) -> StepParams {
+    StepParams::ShowCustomDialog {
+        title: calc(options, 128, file),
+        message: calc(options, 129, file),
+        buttons: (130..=132).filter_map(|option| text(options, option)).collect(),
+        inputs: (133..=135).filter_map(|option| field(options, option, file)).collect(),
+    }
+}
+
+/* Steps with a target field, the only ones whose key 2 data is a field reference. */
+fn takes_field(opcode: &Instruction) -> bool {
+    matches!(opcode,
+        Instruction::GoToField | Instruction::InsertFromIndex | Instruction::InsertFromLastVisited
+        | Instruction::InsertCurrentDate | Instruction::InsertCurrentTime | Instruction::InsertCurrentUserName
+        | Instruction::InsertText | Instruction::InsertCalculatedResult | Instruction::InsertPicture
+        | Instruction::InsertFile | Instruction::InsertPdf | Instruction::InsertAudioVideo
+        | Instruction::InsertFromUrl | Instruction::InsertFromDevice | Instruction::Cut | Instruction::Copy
+        | Instruction::Paste | Instruction::Clear | Instruction::RelookupFieldContents
+        | Instruction::ReplaceFieldContents | Instruction::SetNextSerialValue | Instruction::SetSelection
+        | Instruction::ExportFieldContents | Instruction::SortRecordsByField)
+}
+
+/* Everything under the step in order, split into text, fields, calculations and undecoded ids. */
+fn other(opcode: &Instruction, options: &StepOptions, file: &FmpFile) -> StepParams {
+    let (mut text, mut fields, mut calculations, mut ids) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for ((path, key), data) in options {
+        match (path.as_slice(), key) {
+            ([_], 1) => text.push(fm_string_decrypt(data)),
+            ([_], 2) if takes_field(opcode) => fields.push(decode_field_reference(data, file)),
+            ([_], 2) => ids.push(data.clone()),
+            ([_, 5], 5) => calculations.push(decode_resolved(data, file)),
+            _ => {},
+        }
+    }
+    if text.is_empty() && fields.is_empty() && calculations.is_empty() && ids.is_empty() {
+        return StepParams::None;
+    }
+    StepParams::Other { text, fields, calculations, ids }
+}
+
+/// Decode the options stored below a step's directory for the step's instruction.
+pub(crate) fn decode_step_params(opcode: &Instruction, options: &StepOptions, file: &FmpFile) -> StepParams {
+    match opcode {
+        _ if options.is_empty() => StepParams::None,
+        Instruction::BlankLineComment => StepParams::Comment { text: text(options, 128).unwrap_or_default() },
+        Instruction::If | Instruction::ElseIf | Instruction::ExitLoopIf | Instruction::ExitScript => {
+            StepParams::Calculation { calc: calc(options, 128, file) }
+        },
+        Instruction::SetVariable => StepParams::SetVariable {
+            /* Older files keep the name as a string under the calculation key. */
+            name: text(options, 128)
+                .or_else(|| options.get(&(vec![128, 5], 5)).map(|data| fm_string_decrypt(data)))
+                .unwrap_or_default(),
+            value: calc(options, 129, file),
+            repetition: calc(options, 130, file),
+        },
+        Instruction::SetField => StepParams::SetField {
+            target: field(options, 128, file),
+            calc: calc(options, 129, file),
+            repetition: calc(options, 130, file),
+        },
+        Instruction::GoToLayout => go_to_layout(options, file),
+        Instruction::PerformScript => perform_script(options, file),
+        Instruction::ShowCustomDialog => show_custom_dialog(options, file),
+        _ => other(opcode, options, file),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::calc_engine::calc::Calc;
+    use crate::decompile::steps::*;
+    use crate::repr::component::{FMComponentField, FMComponentLayout, FMComponentScript, FMComponentTable, FMComponentTableOccurence};
+    use crate::util::test_util::encrypt;
+
+    /* Field 2 of table occurrence 4, named if the file knows it */
+    fn total(name: Option<&str>) -> FieldRef {
+        FieldRef { table_occurrence: 4, field: 2, name: name.map(str::to_string) }
+    }
+
+    #[test]
+    fn step_params() {
+        let mut file = FmpFile::new();
+        let mut layout = FMComponentLayout::new();
+        layout.layout_name = "Invoices".to_string();
+        file.layouts.insert(3, layout);
+        let mut script = FMComponentScript::new();
+        script.script_name = "Print".to_string();
+        file.scripts.insert(7, script);
+        let field = vec![0x16, 0, 4, 0, 2];
+
+        let options = StepOptions::from([((vec![128], 2), vec![1, 0, 3]), ((vec![129], 2), vec![11])]);
+        let params = decode_step_params(&Instruction::GoToLayout, &options, &file);
+        assert_eq!(params, StepParams::GoToLayout {
+            target: LayoutTarget::Layout { id: 3, name: "Invoices".to_string() },
+            animation: Animation::CrossDissolve,
+        });
+        assert_eq!(params.switches(), ["\"Invoices\"", "Animation: Cross Dissolve"]);
+        assert_eq!(Animation::from(40).to_string(), "<animation 40>");
+
+        let options = StepOptions::from([((vec![128], 2), vec![0, 7, 0, 0]), ((vec![129, 5], 5), field.clone())]);
+        let params = decode_step_params(&Instruction::PerformScript, &options, &file);
+        assert_eq!(params, StepParams::PerformScript {
+            script: 7,
+            script_name: "Print".to_string(),
+            from_file: None,
+            parameter: Some(Ok(Calc::Field(total(None)))),
+        });
+
+        let options = StepOptions::from([
+            ((vec![128, 5], 5), field.clone()),
+            ((vec![130], 1), encrypt("OK")),
+            ((vec![131], 1), encrypt("Cancel")),
+            ((vec![133], 2), vec![0, 4, 0, 2]),
+        ]);
+        let params = decode_step_params(&Instruction::ShowCustomDialog, &options, &file);
+        assert_eq!(params.switches(), ["<unresolved 4:2>", "Buttons: OK, Cancel", "Input: <unresolved 4:2>"]);
+        let StepParams::ShowCustomDialog { title, message, buttons, inputs } = params else { panic!() };
+        assert_eq!((title, message), (Some(Ok(Calc::Field(total(None)))), None));
+        assert_eq!(buttons, ["OK", "Cancel"]);
+        assert_eq!(inputs, [total(None)]);
+
+        let options = StepOptions::from([((vec![128], 2), vec![0, 4, 0, 2]), ((vec![130, 5], 5), field)]);
+        let params = decode_step_params(&Instruction::SetField, &options, &file);
+        assert_eq!(params.switches(), ["<unresolved 4:2>[<unresolved 4:2>]"]);
+        assert_eq!(decode_step_params(&Instruction::Beep, &StepOptions::new(), &file), StepParams::None);
+    }
+
+    #[test]
+    fn field_and_variable_params() {
+        /* Occurrence 4 of table 1, whose field 2 is Total */
+        let mut file = FmpFile::new();
+        let mut occurrence = FMComponentTableOccurence::new();
+        occurrence.table_occurence_name = "Invoices".to_string();
+        occurrence.table_actual = 1;
+        file.table_occurrences.insert(4, occurrence);
+        let mut table = FMComponentTable::new();
+        let mut field = FMComponentField::new();
+        field.field_name = "Total".to_string();
+        table.fields.insert(2, field);
+        file.tables.insert(1, table);
+        let field = vec![0x16, 0, 4, 0, 2];
+
+        let options = StepOptions::from([
+            ((vec![128], 2), vec![0, 4, 0, 2]),
+            ((vec![129, 5], 5), field.clone()),
+            ((vec![130, 5], 5), field.clone()),
+        ]);
+        let params = decode_step_params(&Instruction::SetField, &options, &file);
+        assert_eq!(params, StepParams::SetField {
+            target: Some(total(Some("Invoices::Total"))),
+            calc: Some(Ok(Calc::Field(total(Some("Invoices::Total"))))),
+            repetition: Some(Ok(Calc::Field(total(Some("Invoices::Total"))))),
+        });
+        assert_eq!(params.switches(), ["Invoices::Total[Invoices::Total]", "Invoices::Total"]);
+
+        let options = StepOptions::from([((vec![128], 1), encrypt("$total")), ((vec![129, 5], 5), field.clone())]);
+        let params = decode_step_params(&Instruction::SetVariable, &options, &file);
+        assert_eq!(params.switches(), ["$total", "Invoices::Total"]);
+        let options = StepOptions::from([((vec![128, 5], 5), encrypt("$old"))]);
+        let StepParams::SetVariable { name, .. } = decode_step_params(&Instruction::SetVariable, &options, &file) else { panic!() };
+        assert_eq!(name, "$old");
+
+        let options = StepOptions::from([((vec![128, 5], 5), field.clone())]);
+        assert_eq!(decode_step_params(&Instruction::ExitLoopIf, &options, &file),
+                   StepParams::Calculation { calc: Some(Ok(Calc::Field(total(Some("Invoices::Total"))))) });
+
+        /* Steps without their own variant keep text, fields and calculations in that order */
+        let options = StepOptions::from([
+            ((vec![128], 1), encrypt("PDF")),
+            ((vec![129], 2), vec![0, 4, 0, 2]),
+            ((vec![130, 5], 5), vec![0x16, 0, 4, 0, 3]),
+        ]);
+        let params = decode_step_params(&Instruction::InsertText, &options, &file);
+        assert_eq!(params.switches(), ["PDF", "Invoices::Total", "<unresolved 4:3>"]);
+
+        /* Key 2 of steps not known to take a field stays undecoded */
+        let params = decode_step_params(&Instruction::Beep, &options, &file);
+        assert_eq!(params.switches(), ["PDF", "<unresolved 4:3>"]);
+        let StepParams::Other { fields, ids, .. } = params else { panic!() };
+        assert_eq!((fields, ids), (vec![], vec![vec![0, 4, 0, 2]]));
+    }
+}
@@ -0,0 +1,59 @@
+use std::fmt;
+use std::io;
+
+/// Everything that can go wrong while decompiling an FMP12 file.
+#[derive(Debug)]
+pub enum FmpError {
+    /// The underlying file or reader failed.
+    Io(io::Error),
+    /// The file does not start with a usable FMP12 header.
+    BadHeader(&'static str),
+    /// A sector, or a chunk inside it, runs past the end of the available data.
+    TruncatedSector { sector: usize, offset: usize },
+    /// A chunk started with an opcode the chunk parser does not understand.
+    UnknownChunk { opcode: u8, sector: usize, offset: usize },
+    /// The sector chain points outside the file or loops back on itself.
+    InvalidSectorChain { sector: usize },
+    /// Data was found for a component that was never declared. Recorded in
+    /// `FmpFile::diagnostics` with the data skipped rather than returned.
+    DanglingReference { component: &'static str, id: usize },
+    /// Records were asked for, but the file they are read from cannot be reopened as it was decompiled.
+    SourceUnavailable(&'static str),
+}
+
+impl fmt::Display for FmpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FmpError::Io(e) => write!(f, "i/o error: {}", e),
+            FmpError::BadHeader(reason) => write!(f, "bad file header: {}", reason),
+            FmpError::TruncatedSector { sector, offset } => {
+                write!(f, "sector {} is truncated at offset {}", sector, offset)
+            },
+            FmpError::UnknownChunk { opcode, sector, offset } => {
+                write!(f, "unknown chunk opcode {:#04x} in sector {} at offset {}", opcode, sector, offset)
+            },
+            FmpError::InvalidSectorChain { sector } => {
+                write!(f, "invalid sector chain at sector {}", sector)
+            },
+            FmpError::DanglingReference { component, id } => {
+                write!(f, "reference to undeclared {} {}", component, id)
+            },
+            FmpError::SourceUnavailable(reason) => write!(f, "cannot read records: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for FmpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FmpError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for FmpError {
+    fn from(e: io::Error) -> Self {
+        FmpError::Io(e)
+    }
+}
@@ -1,3 +1,4 @@
+use crate::error::FmpError;
 use crate::util::format_decode::{get_int, get_path_int};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -17,7 +18,7 @@ pub struct Chunk<'a> {
     pub code: u16,
     pub data: Option<&'a [u8]>,
     pub ref_data: Option<&'a [u8]>,
-    pub path: Vec::<String>,
+    pub path: Vec::<usize>,
     pub segment_idx: Option<u8>,
     pub ref_simple: Option<u16>,
 }
@@ -27,7 +28,7 @@ impl<'a> Chunk<'a> {
            code: u16,
            data: Option<&'a [u8]>,
            ref_data: Option<&'a [u8]>,
-           path: Vec::<String>,
+           path: Vec::<usize>,
            segment_idx: Option<u8>,
            ref_simple: Option<u16>,
         ) -> Self {
@@ -43,231 +44,226 @@ impl<'a> Chunk<'a> {
     }
 }
 
-pub fn get_chunk_from_code<'a>(code: &'a[u8], offset: &mut usize, path: &mut Vec<String>, local : usize) -> Result<Chunk<'a>, &'static str> {
-    let mut chunk_code = code[*offset];
-    let mut ctype = ChunkType::Noop;
+/* Bounds-checked view of `len` bytes at `at`, errors are reported against `sector`. */
+fn take(code: &[u8], at: usize, len: usize, sector: usize) -> Result<&[u8], FmpError> {
+    code.get(at..at + len).ok_or(FmpError::TruncatedSector { sector, offset: at })
+}
+
+fn byte_at(code: &[u8], at: usize, sector: usize) -> Result<u8, FmpError> {
+    take(code, at, 1, sector).map(|b| b[0])
+}
+
+/// Decode the chunk at `offset` within the sector buffer `code`, updating the
+/// current directory `path` as path push/pop chunks are found.
+pub fn get_chunk_from_code<'a>(code: &'a[u8], offset: &mut usize, path: &mut Vec<usize>, sector: usize) -> Result<Chunk<'a>, FmpError> {
+    let mut chunk_code = byte_at(code, *offset, sector)?;
+    let ctype;
     let mut data: Option<&[u8]> = None;
     let mut ref_data: Option<&[u8]> = None;
     let mut segidx: Option<u8> = None;
     let mut ref_simple: Option<u16> = None;
     let mut delayed = false;
-    
-    let table_idx = 0;
 
     if (chunk_code & 0xC0) == 0xC0 {
         chunk_code &= 0x3F;
         delayed = true;
     }
 
-    // println!("offset: {}, Code: {:x}", offset, chunk_code);
-
     match chunk_code {
         0x00 => {
             *offset += 1;
             ctype = ChunkType::DataSimple;
-            data = Some(&code[*offset..*offset]);
-            if code[1] == 0x00 { }
+            data = Some(take(code, *offset, 0, sector)?);
             *offset += 1;
         },
-        0x01 | 0x02 | 0x03 | 0x04 | 0x05 => {
+        0x01..=0x05 => {
             *offset += 1;
             ctype = ChunkType::RefSimple;
-            ref_simple = Some(code[*offset] as u16);
+            ref_simple = Some(byte_at(code, *offset, sector)? as u16);
             *offset += 1;
             let len = (chunk_code == 0x01) as usize + (2 * (chunk_code - 0x01) as usize);
-            data = Some(&code[*offset..*offset+len]);
+            data = Some(take(code, *offset, len, sector)?);
             *offset += len;
         }
         0x06 => {
             *offset += 1;
             ctype = ChunkType::RefSimple;
-            ref_simple = Some(code[*offset] as u16);
+            ref_simple = Some(byte_at(code, *offset, sector)? as u16);
             *offset += 1;
-            let len = code[*offset] as usize;
+            let len = byte_at(code, *offset, sector)? as usize;
             *offset += 1;
-            data = Some(&code[*offset..*offset+len]);
-            *offset += data.unwrap().len();
+            data = Some(take(code, *offset, len, sector)?);
+            *offset += len;
         },
         0x07 => {
             *offset += 1;
             ctype = ChunkType::DataSegment;
-            segidx = Some(code[*offset]);
+            segidx = Some(byte_at(code, *offset, sector)?);
             *offset += 1;
-            let len = get_int(&code[*offset..*offset+2]);
+            let len = get_int(take(code, *offset, 2, sector)?);
             *offset += 2;
-            data = Some(&code[*offset..*offset+len]);
+            data = Some(take(code, *offset, len, sector)?);
             *offset += len;
         },
         0x08 => {
             *offset += 1;
             ctype = ChunkType::DataSimple;
-            data = Some(&code[*offset..*offset+2]);
+            data = Some(take(code, *offset, 2, sector)?);
             *offset += 2;
         },
-        0x09 | 0x0A | 0x0B | 0x0C | 0x0D => {
+        0x09..=0x0D => {
             *offset += 1;
             ctype = ChunkType::RefSimple;
-            ref_simple = Some(get_path_int(&code[*offset..*offset+2]) as u16);
+            ref_simple = Some(get_path_int(take(code, *offset, 2, sector)?) as u16);
             *offset += 2;
             let len = (chunk_code == 0x09) as usize + (2 *(chunk_code - 0x09) as usize);
-            data = Some(&code[*offset..*offset+len]);
+            data = Some(take(code, *offset, len, sector)?);
             *offset += len;
         },
         0x0E => {
-            if code[*offset + 1] != 0xFF {
+            if byte_at(code, *offset + 1, sector)? != 0xFF {
                 *offset += 1;
                 ctype = ChunkType::RefSimple;
-                ref_simple = Some(get_path_int(&code[*offset..*offset+2]) as u16);
+                ref_simple = Some(get_path_int(take(code, *offset, 2, sector)?) as u16);
                 *offset += 2;
-                let len = code[*offset] as usize;
+                let len = byte_at(code, *offset, sector)? as usize;
                 *offset += 1;
-                data = Some(&code[*offset..*offset+len]);
+                data = Some(take(code, *offset, len, sector)?);
                 *offset += len;
             } else {
                 *offset += 1;
                 ctype = ChunkType::DataSimple;
-                data = Some(&code[*offset..*offset+6]);
+                data = Some(take(code, *offset, 6, sector)?);
                 *offset += 6;
             }
         },
-        0x0F => {
-            if code[*offset+1] == 0x80 {
-                ctype = ChunkType::DataSegment;
-                *offset += 2;
-                segidx = Some(code[*offset]);
-                *offset += 1;
-                let len = get_int(&code[*offset..*offset+2]);
-                *offset += 2;
-                data = Some(&code[*offset..*offset+len]);
-                *offset += len;
-            }
+        0x0F if byte_at(code, *offset + 1, sector)? == 0x80 => {
+            ctype = ChunkType::DataSegment;
+            *offset += 2;
+            segidx = Some(byte_at(code, *offset, sector)?);
+            *offset += 1;
+            let len = get_int(take(code, *offset, 2, sector)?);
+            *offset += 2;
+            data = Some(take(code, *offset, len, sector)?);
+            *offset += len;
         },
         0x10 => {
             *offset += 1;
             ctype = ChunkType::DataSimple;
-            data = Some(&code[*offset..*offset+3]);
+            data = Some(take(code, *offset, 3, sector)?);
             *offset += 3;
         },
-        0x11 | 0x12 | 0x13 | 0x14 | 0x15 => {
+        0x11..=0x15 => {
             *offset += 1;
             ctype = ChunkType::DataSimple;
             let len = 3 + (chunk_code == 0x11) as usize + (2 * (chunk_code as usize - 0x11));
-            data = Some(&code[*offset..*offset+len]);
+            data = Some(take(code, *offset, len, sector)?);
             *offset += len;
         },
         0x16 => {
             *offset += 1;
             ctype = ChunkType::RefLong;
-            ref_data = Some(&code[*offset..*offset+3]);
+            ref_data = Some(take(code, *offset, 3, sector)?);
             *offset += 3;
-            let len = code[*offset] as usize;
+            let len = byte_at(code, *offset, sector)? as usize;
             *offset += 1;
-            data = Some(&code[*offset..*offset+len]);
+            data = Some(take(code, *offset, len, sector)?);
             *offset += len;
         }
         0x17 => {
             *offset += 1;
             ctype = ChunkType::RefLong;
-            ref_data = Some(&code[*offset..*offset+3]);
+            ref_data = Some(take(code, *offset, 3, sector)?);
             *offset += 3;
-            let len = get_path_int(&code[*offset..*offset+2]);
+            let len = get_path_int(take(code, *offset, 2, sector)?);
             *offset += 2;
-            data = Some(&code[*offset..*offset+len]);
+            data = Some(take(code, *offset, len, sector)?);
             *offset += len;
         },
-        0x1B => {
-            if code[*offset + 1] == 0x00 {
-                *offset += 2;
-                ctype = ChunkType::RefSimple;
-                ref_simple = Some(code[*offset] as u16);
-                *offset += 1;
-                data = Some(&code[*offset..*offset+4]);
-                *offset += 4;
-            } else {
-                *offset += 1;
-                ctype = ChunkType::DataSimple;
-                let len = code[*offset] as usize;
-                *offset += 1;
-                data = Some(&code[*offset..*offset+len]);
-                *offset += len + (chunk_code == 0x19) as usize + (2 * (chunk_code as usize - 0x19));
-            }
+        0x1B if byte_at(code, *offset + 1, sector)? == 0x00 => {
+            *offset += 2;
+            ctype = ChunkType::RefSimple;
+            ref_simple = Some(byte_at(code, *offset, sector)? as u16);
+            *offset += 1;
+            data = Some(take(code, *offset, 4, sector)?);
+            *offset += 4;
         },
-        0x19 | 0x1A | 0x1C | 0x1D => {
+        0x19..=0x1D => {
             *offset += 1;
             ctype = ChunkType::DataSimple;
-            let len = code[*offset] as usize;
+            let len = byte_at(code, *offset, sector)? as usize;
             *offset += 1;
-            data = Some(&code[*offset..*offset+len]);
+            data = Some(take(code, *offset, len, sector)?);
             *offset += len + (chunk_code == 0x19) as usize + (2 * (chunk_code as usize - 0x19));
         },
         0x1E => {
             *offset += 1;
             ctype = ChunkType::RefLong;
-            let ref_len = code[*offset] as usize;
+            let ref_len = byte_at(code, *offset, sector)? as usize;
             *offset += 1;
-            ref_data = Some(&code[*offset..*offset+ref_len]);
+            ref_data = Some(take(code, *offset, ref_len, sector)?);
             *offset += ref_len;
-            let len = code[*offset] as usize;
+            let len = byte_at(code, *offset, sector)? as usize;
             *offset += 1;
-            data = Some(&code[*offset..*offset+len]);
+            data = Some(take(code, *offset, len, sector)?);
             *offset += len;
         },
         0x1F => {
             *offset += 1;
             ctype = ChunkType::RefLong;
-            let ref_len = code[*offset] as usize;
+            let ref_len = byte_at(code, *offset, sector)? as usize;
             *offset += 1;
-            ref_data = Some(&code[*offset..*offset+ref_len]);
-            let len = get_int(&code[*offset..*offset+2]);
+            ref_data = Some(take(code, *offset, ref_len, sector)?);
+            *offset += ref_len;
+            let len = get_int(take(code, *offset, 2, sector)?);
             *offset += 2;
-            data = Some(&code[*offset..*offset+len]);
+            data = Some(take(code, *offset, len, sector)?);
             *offset += len;
         },
         0x20 => {
             *offset += 1;
             ctype = ChunkType::PathPush;
-            if code[*offset] == 0xFE {
+            if byte_at(code, *offset, sector)? == 0xFE {
                 *offset += 1;
-                data = Some(&code[*offset..*offset+8]);
+                data = Some(take(code, *offset, 8, sector)?);
             } else {
-                data = Some(&code[*offset..*offset+1]);
+                data = Some(take(code, *offset, 1, sector)?);
             }
-            let idx = get_path_int(&code[*offset..*offset+1]);
-            *offset += data.unwrap().len();
-            path.push(idx.to_string());
+            let idx = get_path_int(take(code, *offset, 1, sector)?);
+            *offset += data.map_or(0, |d| d.len());
+            path.push(idx);
         },
         0x23 => {
             *offset += 1;
             ctype = ChunkType::DataSimple;
-            let len = code[*offset] as usize;
+            let len = byte_at(code, *offset, sector)? as usize;
             *offset += 1;
-            data = Some(&code[*offset..*offset+len]);
+            data = Some(take(code, *offset, len, sector)?);
             *offset += len;
         },
         0x28 => {
             *offset += 1;
             ctype = ChunkType::PathPush;
-            data = Some(&code[*offset..*offset+2]);
-            let idx = get_path_int(&code[*offset..*offset+2]);
+            data = Some(take(code, *offset, 2, sector)?);
+            let idx = get_path_int(take(code, *offset, 2, sector)?);
             *offset += 2;
-            path.push(idx.to_string());
+            path.push(idx);
         },
         0x30 => {
             *offset += 1;
             ctype = ChunkType::PathPush;
-            data = Some(&code[*offset..*offset+3]);
-            // let dir = 0x80 + ((code[*offset + 1] as usize) << 8) + code[*offset + 2] as usize;
-            let dir = get_path_int(&code[*offset..*offset+3]).to_string();
-            path.push(dir.to_string());
+            data = Some(take(code, *offset, 3, sector)?);
+            let dir = get_path_int(take(code, *offset, 3, sector)?);
+            path.push(dir);
             *offset += 3;
         },
         0x38 => {
             *offset += 1;
             ctype = ChunkType::PathPush;
-            let len = code[*offset] as usize;
+            let len = byte_at(code, *offset, sector)? as usize;
             *offset += 1;
-            data = Some(&code[*offset..*offset+2]);
-            path.push(get_path_int(&code[*offset..*offset+2]).to_string());
+            data = Some(take(code, *offset, 2, sector)?);
+            path.push(get_path_int(take(code, *offset, 2, sector)?));
             *offset += len;
         },
         0x3D | 0x40 => {
@@ -280,22 +276,82 @@ pub fn get_chunk_from_code<'a>(code: &'a[u8], offset: &mut usize, path: &mut Vec
             *offset += 1;
         }
         _ => {
-            eprintln!("Unknown code @ B:{}, O:{}: {:x}", (4096 / (*offset - local)), local, chunk_code);
-            return Err("Invalid Opcode for Chunk.");
+            return Err(FmpError::UnknownChunk {
+                opcode: chunk_code,
+                sector,
+                offset: *offset,
+            });
         }
     };
 
-    if delayed == true {
+    if delayed {
         path.pop();
     }
-    return Ok(Chunk::new(ctype,
-                      chunk_code.into(),
-                      data,
-                      ref_data,
-                      path.clone(),
-                      segidx,
-                      ref_simple));
+    Ok(Chunk::new(ctype,
+                  chunk_code.into(),
+                  data,
+                  ref_data,
+                  path.clone(),
+                  segidx,
+                  ref_simple))
 }
 
+#[cfg(test)]
+mod tests {
+    use crate::error::FmpError;
+    use crate::fmp_format::chunk::*;
+
+    #[test]
+    fn chunk_errors() {
+        let mut path = vec![];
+        let mut offset = 0;
+        assert!(matches!(get_chunk_from_code(&[0x06, 0x10, 0x05, 0x3f], &mut offset, &mut path, 0),
+            Err(FmpError::TruncatedSector { sector: 0, offset: 3 })));
 
+        let mut offset = 1;
+        assert!(matches!(get_chunk_from_code(&[0x80, 0x2a], &mut offset, &mut path, 7),
+            Err(FmpError::UnknownChunk { opcode: 0x2a, sector: 7, offset: 1 })));
+    }
+
+    #[test]
+    fn path_tracking() {
+        let code = [0x20, 0x03, 0x28, 0x80, 0x8a, 0x06, 0x10, 0x02, 0x32, 0x33, 0x40];
+        let mut path = vec![];
+        let mut offset = 0;
+        get_chunk_from_code(&code, &mut offset, &mut path, 0).unwrap();
+        get_chunk_from_code(&code, &mut offset, &mut path, 0).unwrap();
+        assert_eq!(path, vec![3, 266]);
+        let chunk = get_chunk_from_code(&code, &mut offset, &mut path, 0).unwrap();
+        assert_eq!(chunk.ref_simple, Some(16));
+        assert_eq!(chunk.data, Some(&[0x32, 0x33][..]));
+        get_chunk_from_code(&code, &mut offset, &mut path, 0).unwrap();
+        assert_eq!(path, vec![3]);
+        assert_eq!(offset, code.len());
+    }
 
+    #[test]
+    fn two_byte_path_components() {
+        /* The low 7 bits of the first byte are the high byte, offset by 0x80. */
+        let code = [0x28, 0x81, 0x02, 0x28, 0x80, 0x8a];
+        let mut path = vec![];
+        let mut offset = 0;
+        get_chunk_from_code(&code, &mut offset, &mut path, 0).unwrap();
+        get_chunk_from_code(&code, &mut offset, &mut path, 0).unwrap();
+        assert_eq!(path, vec![0x80 + 0x100 + 2, 0x80 + 0x8a]);
+    }
+
+    #[test]
+    fn long_reference_chunks() {
+        /* 0x1F: reference length, reference, 2 byte data length, data; then a simple key */
+        let code = [0x1f, 0x02, 0xaa, 0xbb, 0x00, 0x03, 0x01, 0x02, 0x03, 0x06, 0x10, 0x01, 0x32];
+        let mut path = vec![];
+        let mut offset = 0;
+        let chunk = get_chunk_from_code(&code, &mut offset, &mut path, 0).unwrap();
+        assert_eq!(chunk.ref_data, Some(&[0xaa, 0xbb][..]));
+        assert_eq!(chunk.data, Some(&[0x01, 0x02, 0x03][..]));
+        assert_eq!(offset, 9);
+        let chunk = get_chunk_from_code(&code, &mut offset, &mut path, 0).unwrap();
+        assert_eq!((chunk.ref_simple, chunk.data), (Some(16), Some(&[0x32][..])));
+        assert_eq!(offset, code.len());
+    }
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use crate::error::FmpError;
+
+const MAGIC : [u8; 15] = [0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01, 0x00, 0x05, 0x00, 0x02, 0x00, 0x02, 0xC0];
+const SIGNATURE : &[u8] = b"HBAM";
+const SIGNATURE_OFFSET : usize = 15;
+const VERSION_DATE_OFFSET : usize = 531;
+const VERSION_DATE_LEN : usize = 7;
+const APPLICATION_OFFSET : usize = 541;
+const FILENAME_OFFSET : usize = 1024;
+
+/// Metadata stored in sector 0 of every FMP12 file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FileHeader {
+    /// Version of the HBAM file format. FMP12 files are version 7.
+    pub format_version: u8,
+    /// Build date of the application that last saved the file, e.g. `12Nov15`.
+    pub version_date: String,
+    /// Application that last saved the file, e.g. `Pro 19.0`.
+    pub application_version: String,
+    /// Name the file had when it was created.
+    pub original_filename: String,
+}
+
+/* Strings in the header are plain text with a single length byte in front. */
+fn get_pascal_string(sector: &[u8], offset: usize) -> Result<String, FmpError> {
+    let len = *sector.get(offset).ok_or(FmpError::BadHeader("header sector is truncated"))? as usize;
+    let bytes = sector.get(offset + 1..offset + 1 + len).ok_or(FmpError::BadHeader("header sector is truncated"))?;
+    Ok(String::from_utf8_lossy(bytes).to_string())
+}
+
+pub fn get_header(sector: &[u8]) -> Result<FileHeader, FmpError> {
+    if sector.get(0..MAGIC.len()) != Some(&MAGIC[..]) {
+        return Err(FmpError::BadHeader("missing FMP12 magic number"));
+    }
+    let signature = sector.get(SIGNATURE_OFFSET..SIGNATURE_OFFSET + SIGNATURE.len() + 1)
+        .ok_or(FmpError::BadHeader("header sector is truncated"))?;
+    if &signature[..SIGNATURE.len()] != SIGNATURE {
+        return Err(FmpError::BadHeader("missing HBAM signature"));
+    }
+    let format_version = signature[SIGNATURE.len()].wrapping_sub(b'0');
+    if format_version != 7 {
+        return Err(FmpError::BadHeader("unsupported HBAM version, only FMP12 files are supported"));
+    }
+
+    let version_date = sector.get(VERSION_DATE_OFFSET..VERSION_DATE_OFFSET + VERSION_DATE_LEN)
+        .ok_or(FmpError::BadHeader("header sector is truncated"))?;
+
+    Ok(FileHeader {
+        format_version,
+        version_date: String::from_utf8_lossy(version_date).to_string(),
+        application_version: get_pascal_string(sector, APPLICATION_OFFSET)?,
+        original_filename: get_pascal_string(sector, FILENAME_OFFSET)?,
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::error::FmpError;
+    use crate::fmp_format::header::*;
+
+    pub fn test_header(app: &str, filename: &str) -> Vec<u8> {
+        let mut sector = vec![0u8; 4096];
+        sector[0..15].copy_from_slice(&MAGIC);
+        sector[15..20].copy_from_slice(b"HBAM7");
+        sector[531..538].copy_from_slice(b"12Nov15");
+        sector[541] = app.len() as u8;
+        sector[542..542 + app.len()].copy_from_slice(app.as_bytes());
+        sector[1024] = filename.len() as u8;
+        sector[1025..1025 + filename.len()].copy_from_slice(filename.as_bytes());
+        sector
+    }
+
+    #[test]
+    fn header_testing() {
+        let header = get_header(&test_header("Pro 19.0", "Invoices.fmp12")).unwrap();
+        assert_eq!(header.format_version, 7);
+        assert_eq!(header.version_date, "12Nov15");
+        assert_eq!(header.application_version, "Pro 19.0");
+        assert_eq!(header.original_filename, "Invoices.fmp12");
+
+        let mut fp7 = test_header("Pro 11.0", "Old.fp7");
+        fp7[19] = b'5';
+        assert!(matches!(get_header(&fp7), Err(FmpError::BadHeader(_))));
+        assert!(matches!(get_header(b"%PDF-1.7"), Err(FmpError::BadHeader(_))));
+    }
+}
@@ -1,5 +1,33 @@
 pub const FIELD_TYPE : u16 = 2;
 pub const COMPONENT_DESC : u16 = 3;
+pub const FIELD_CONTEXT : u16 = 4;
+pub const CALCULATION : u16 = 5;
+pub const FIELD_SUMMARY : u16 = 8;
+pub const VALIDATION_RANGE_FROM : u16 = 20;
+pub const VALIDATION_RANGE_TO : u16 = 21;
+pub const VALIDATION_VALUE_LIST : u16 = 22;
+pub const VALIDATION_MAX_CHARACTERS : u16 = 23;
+pub const VALIDATION_MESSAGE : u16 = 24;
+pub const AUTO_ENTER_DATA : u16 = 25;
+pub const AUTO_ENTER_SERIAL_NEXT : u16 = 26;
+pub const AUTO_ENTER_SERIAL_INCREMENT : u16 = 27;
+pub const AUTO_ENTER_LOOKUP : u16 = 28;
 pub const COMPONENT_NAME : u16 = 16;
 pub const CREATOR_ACCOUNT_NAME : u16 = 129;
 pub const CREATOR_USER_NAME : u16 = 130;
+pub const VALUE_LIST_SWITCHES : u16 = 2;
+pub const VALUE_LIST_CUSTOM_VALUES : u16 = 4;
+pub const VALUE_LIST_PRIMARY_FIELD : u16 = 5;
+pub const VALUE_LIST_SECONDARY_FIELD : u16 = 6;
+pub const VALUE_LIST_RELATED_FROM : u16 = 7;
+pub const VALUE_LIST_DATA_SOURCE : u16 = 8;
+pub const LAYOUT_SWITCHES : u16 = 2;
+pub const OBJECT_FIELD : u16 = 4;
+pub const OBJECT_TEXT : u16 = 5;
+pub const OBJECT_TABLE_OCCURRENCE : u16 = 7;
+pub const CATALOG_SWITCHES : u16 = 2;
+pub const PRIVILEGE_SWITCHES : u16 = 2;
+pub const CUSTOM_FUNCTION_SWITCHES : u16 = 2;
+pub const CUSTOM_FUNCTION_PARAMETERS : u16 = 4;
+pub const DATA_SOURCE_SWITCHES : u16 = 2;
+pub const DATA_SOURCE_PATHS : u16 = 4;
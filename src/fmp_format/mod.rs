@@ -1,3 +1,4 @@
 pub mod chunk;
+pub mod header;
 pub mod metadata_constants;
 pub mod sector;
@@ -1,6 +1,9 @@
+use crate::error::FmpError;
 use crate::util::format_decode::get_int;
 use crate::fmp_format::chunk;
 
+pub const SECTOR_SIZE : usize = 4096;
+
 #[derive(Clone, Default)]
 pub struct Sector<'a> {
     pub deleted: bool,
@@ -30,13 +33,33 @@ impl<'a> Sector<'a> {
     }
 }
 
-pub fn get_sector(sector: &[u8]) -> Sector {
-    Sector::new(
+/// Decode the 20 byte header of sector `idx`, the chunks follow as its payload.
+pub fn get_sector(sector: &[u8], idx: usize) -> Result<Sector<'_>, FmpError> {
+    if sector.len() < 20 {
+        return Err(FmpError::TruncatedSector { sector: idx, offset: sector.len() });
+    }
+    Ok(Sector::new(
         sector[0] != 0,
         sector[1] as u32 & 0x00FFFFFF,
         get_int(&sector[4..8]) as u32,
         get_int(&sector[8..12]),
         &sector[20..],
         Vec::<chunk::Chunk>::new()
-        )
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::FmpError;
+    use crate::fmp_format::sector::*;
+
+    #[test]
+    fn sector_header() {
+        let mut bytes = [0u8; 24];
+        bytes[8..12].copy_from_slice(&[0, 0, 0, 5]);
+        bytes[20] = 0x40;
+        let sector = get_sector(&bytes, 2).unwrap();
+        assert_eq!((sector.deleted, sector.next, sector.payload), (false, 5, &[0x40, 0, 0, 0][..]));
+        assert!(matches!(get_sector(&bytes[..12], 2), Err(FmpError::TruncatedSector { sector: 2, offset: 12 })));
+    }
 }
@@ -1,20 +1,98 @@
+//! Decompiler for FileMaker `.fmp12` files.
+//!
+//! ```no_run
+//! let file = burnfmlib::open("Invoices.fmp12")?;
+//! for script in file.scripts.values() {
+//!     println!("{}", script.script_name);
+//! }
+//! # Ok::<(), burnfmlib::FmpError>(())
+//! ```
+
+mod error;
 mod fmp_format;
 mod util;
 mod decompile;
 mod script_engine;
 mod repr;
+mod calc_engine;
+
+use std::path::Path;
+
+pub use error::FmpError;
+pub use repr::file::FmpFile;
+pub use fmp_format::header::FileHeader;
+pub use repr::component::{
+    FMComponentType,
+    FMComponentField,
+    FMComponentTable,
+    FMComponentScript,
+    FMComponentTableOccurence,
+    FMComponentRelationship,
+    FMComponentLayout,
+    FMComponentValueList,
+    FMComponentAccount,
+    FMComponentPrivilegeSet,
+    FMComponentExtendedPrivilege,
+    FMComponentCustomFunction,
+    FMComponentDataSource,
+};
+pub use repr::field::{
+    FieldKind,
+    DataType,
+    AutoEnter,
+    AutoEnterPreset,
+    SerialNumber,
+    Lookup,
+    Validation,
+    ValidationRange,
+    StrictType,
+    IndexLanguage,
+    Storage,
+    SummaryOp,
+    FieldSummary,
+};
+pub use repr::relationship::{Comparison, Predicate, SortField, JoinOptions};
+pub use repr::value_list::{ValueListSource, ValueListSort, ValueListField, RelatedFilter};
+pub use repr::layout::{LayoutPart, PartKind, LayoutObject, ObjectKind, Bounds, FieldTarget};
+pub use repr::script_tree::ScriptTreeItem;
+pub use repr::trigger::{Trigger, TriggerEvent, TriggerSource};
+pub use repr::custom_function::Availability;
+pub use repr::data_source::{BaseTable, DataSourcePath, DataSourceType, PathPrefix};
+pub use repr::security::{AccountType, AccessLevel, RecordAccess};
+pub use repr::record::{Record, Records, Value, Date, Time, ContainerRef};
+pub use repr::solution::{Solution, CrossFileReference, ReferenceSource};
+pub use repr::graph::{RelationshipGraph, GraphNode, GraphEdge};
+pub use script_engine::instructions::{Instruction, Script, ScriptStep};
+pub use script_engine::step_params::{StepParams, StepCalc, LayoutTarget, Animation};
+pub use calc_engine::calc::{Calc, BinaryOp, UnaryOp, FieldRef};
+pub use calc_engine::decode::{decode_calculation, CalcError};
+pub use calc_engine::functions::{get_function, Function, FUNCTIONS};
+pub use calc_engine::number::{Decimal, ParseDecimalError};
+pub use script_engine::script_text::{script_to_text, step_to_text};
+pub use decompile::decompiler::{decompile_fmp12_file, decompile_fmp12_bytes, decompile_fmp12_reader, read_fmp12_header};
+#[allow(deprecated)]
+pub use decompile::decompiler::decompile_fmp12_file_with_header;
 
-pub fn add(left: usize, right: usize) -> usize {
-    left + right
+/// The sector and chunk level machinery the decompiler is built on.
+pub mod raw {
+    pub use crate::fmp_format::chunk::{get_chunk_from_code, Chunk, ChunkType};
+    pub use crate::fmp_format::header::get_header;
+    pub use crate::fmp_format::sector::{get_sector, Sector, SECTOR_SIZE};
+    pub use crate::fmp_format::metadata_constants;
+    pub use crate::util::format_decode::{fm_string_decrypt, get_int, get_path_int};
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Open and decompile the `.fmp12` file at `path`.
+pub fn open<P: AsRef<Path>>(path: P) -> Result<FmpFile, FmpError> {
+    decompile_fmp12_file(path.as_ref())
+}
+
+/// Open every `.fmp12` file at `paths` as one solution, linking their cross-file references.
+pub fn open_solution<P: AsRef<Path>>(paths: &[P]) -> Result<Solution, FmpError> {
+    Solution::open(paths)
+}
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
+/// Read only the header of the `.fmp12` file at `path`, failing if it is not an FMP12 file.
+pub fn read_header<P: AsRef<Path>>(path: P) -> Result<FileHeader, FmpError> {
+    read_fmp12_header(std::fs::File::open(path)?)
 }
@@ -2,6 +2,15 @@
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use crate::script_engine::instructions::ScriptStep;
+use crate::calc_engine::calc::Calc;
+use crate::repr::custom_function::Availability;
+use crate::repr::data_source::{BaseTable, DataSourcePath, DataSourceType};
+use crate::repr::layout::{LayoutObject, LayoutPart};
+use crate::repr::relationship::{JoinOptions, Predicate};
+use crate::repr::security::{AccessLevel, AccountType, RecordAccess};
+use crate::repr::trigger::Trigger;
+use crate::repr::value_list::{RelatedFilter, ValueListField, ValueListSort, ValueListSource};
+use crate::repr::field::{AutoEnter, DataType, FieldKind, FieldSummary, IndexLanguage, Storage, Validation};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum FMComponentType {
@@ -16,28 +25,57 @@ pub enum FMComponentType {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FMComponentField {
-    pub data_type: String,
+    pub data_type: DataType,
     pub field_description: String,
     pub field_name: String,
-    pub field_type: String,
+    pub kind: FieldKind,
+    pub auto_enter: AutoEnter,
+    pub validation: Validation,
+    pub language: IndexLanguage,
+    pub storage: Storage,
+    pub repetitions: u16,
+    /// Formula of a calculation field, `None` if missing or could not be decoded, see `calculation_error`.
+    pub calculation: Option<Calc>,
+    pub calculation_error: Option<String>,
+    /// Table occurrence a calculation field is evaluated from.
+    pub context_occurrence: u16,
+    pub context_occurrence_name: String,
+    pub summary: Option<FieldSummary>,
     pub created_by_account: String,
     pub created_by_user: String,
 }
 impl FMComponentField {
     pub fn new() -> Self {
         Self {
-            data_type: String::new(),
+            data_type: DataType::default(),
             field_description: String::new(),
             field_name: String::new(),
-            field_type: String::new(),
+            kind: FieldKind::default(),
+            auto_enter: AutoEnter::default(),
+            validation: Validation::default(),
+            language: IndexLanguage::default(),
+            storage: Storage::default(),
+            repetitions: 1,
+            calculation: None,
+            calculation_error: None,
+            context_occurrence: 0,
+            context_occurrence_name: String::new(),
+            summary: None,
             created_by_account: String::new(),
             created_by_user: String::new(),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FMComponentTest {
+/* Every field has at least one repetition, so the default has to come from `new`. */
+impl Default for FMComponentField {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct FMComponentTest {
     pub test_name: String,
     pub script: FMComponentScript,
     pub created_by_account: String,
@@ -45,19 +83,7 @@ pub struct FMComponentTest {
     pub assertions: Vec<String>,
 }
 
-impl FMComponentTest {
-    pub fn new() -> Self {
-        Self {
-            test_name: String::new(),
-            script: FMComponentScript::new(),
-            created_by_account: String::new(),
-            create_by_user: String::new(),
-            assertions: vec![],
-        }
-    }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FMComponentTable {
     pub table_name: String,
     pub created_by_account: String,
@@ -89,9 +115,14 @@ impl FMComponentTable {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FMComponentScript {
     pub script_name: String,
+    /// Names of the folders the script sits in, outermost first.
+    pub folder_path: Vec<String>,
+    pub include_in_menu: bool,
+    /// "Run script with full access privileges".
+    pub full_access: bool,
     pub created_by_account: String,
     pub create_by_user: String,
     pub arguments: Vec<String>,
@@ -102,6 +133,9 @@ impl FMComponentScript {
     pub fn new() -> Self {
         Self {
             script_name: String::new(),
+            folder_path: vec![],
+            include_in_menu: false,
+            full_access: false,
             created_by_account: String::new(),
             create_by_user: String::new(),
             arguments: vec![],
@@ -110,11 +144,15 @@ impl FMComponentScript {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FMComponentTableOccurence {
     pub table_occurence_name: String,
+    /// Base table id, in the data source's file when `data_source` is set.
     pub table_actual: u16,
     pub table_actual_name: String,
+    /// External data source holding the base table, `None` for a table of this file.
+    pub data_source: Option<u16>,
+    pub data_source_name: String,
     pub created_by_account: String,
     pub create_by_user: String,
 }
@@ -125,36 +163,55 @@ impl FMComponentTableOccurence {
             table_occurence_name: String::new(),
             table_actual: 0,
             table_actual_name: String::new(),
+            data_source: None,
+            data_source_name: String::new(),
             created_by_account: String::new(),
             create_by_user: String::new()
         }
     }
+
+    pub fn base_table(&self) -> BaseTable {
+        match self.data_source {
+            Some(data_source) => BaseTable::External { data_source, table: self.table_actual },
+            None => BaseTable::Local(self.table_actual),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FMComponentRelationship {
-    pub table1: u16,
+    /// `None` when the relationship neither names its first occurrence nor follows one.
+    pub table1: Option<u16>,
     pub table1_name: String,
+    pub table1_options: JoinOptions,
     pub table2: u16,
     pub table2_name: String,
-    pub comparison: u8,
+    pub table2_options: JoinOptions,
+    pub predicates: Vec<Predicate>,
 }
 
 impl FMComponentRelationship {
     pub fn new() -> Self {
         Self {
-            table1: 0,
+            table1: None,
             table1_name: String::new(),
+            table1_options: JoinOptions::default(),
             table2: 0,
             table2_name: String::new(),
-            comparison: 0,
+            table2_options: JoinOptions::default(),
+            predicates: vec![],
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FMComponentLayout {
     pub layout_name: String,
+    pub table_occurrence: u16,
+    pub table_occurrence_name: String,
+    pub parts: Vec<LayoutPart>,
+    pub objects: Vec<LayoutObject>,
+    pub triggers: Vec<Trigger>,
     pub created_by_account: String,
     pub create_by_user: String,
 }
@@ -163,15 +220,28 @@ impl FMComponentLayout {
     pub fn new() -> Self {
         Self {
             layout_name: String::new(),
+            table_occurrence: 0,
+            table_occurrence_name: String::new(),
+            parts: vec![],
+            objects: vec![],
+            triggers: vec![],
             created_by_account: String::new(),
             create_by_user: String::new()
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FMComponentValueList {
     pub list_name: String,
+    pub source: ValueListSource,
+    pub custom_values: Vec<String>,
+    pub primary_field: Option<ValueListField>,
+    pub secondary_field: Option<ValueListField>,
+    pub related_only: Option<RelatedFilter>,
+    pub sort: ValueListSort,
+    /// Name of the list in the other file for `ExternalFile` sources, once linked in a `Solution`.
+    pub external_list_name: String,
     pub created_by_account: String,
     pub create_by_user: String,
 }
@@ -180,6 +250,145 @@ impl FMComponentValueList {
     pub fn new() -> Self {
         Self {
             list_name: String::new(),
+            source: ValueListSource::default(),
+            custom_values: vec![],
+            primary_field: None,
+            secondary_field: None,
+            related_only: None,
+            sort: ValueListSort::default(),
+            external_list_name: String::new(),
+            created_by_account: String::new(),
+            create_by_user: String::new(),
+        }
+    }
+}
+
+/// An account. Only the account's metadata is read, never its password.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FMComponentAccount {
+    pub account_name: String,
+    pub account_type: AccountType,
+    pub active: bool,
+    pub privilege_set: usize,
+    pub privilege_set_name: String,
+    pub created_by_account: String,
+    pub create_by_user: String,
+}
+
+impl FMComponentAccount {
+    pub fn new() -> Self {
+        Self {
+            account_name: String::new(),
+            account_type: AccountType::default(),
+            active: false,
+            privilege_set: 0,
+            privilege_set_name: String::new(),
+            created_by_account: String::new(),
+            create_by_user: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FMComponentPrivilegeSet {
+    pub set_name: String,
+    pub set_description: String,
+    pub full_access: bool,
+    /// Record access by table id.
+    pub records: HashMap<usize, RecordAccess>,
+    pub layouts: HashMap<usize, AccessLevel>,
+    pub value_lists: HashMap<usize, AccessLevel>,
+    pub scripts: HashMap<usize, AccessLevel>,
+    /// Keywords of the extended privileges enabled for this set, e.g. `fmapp`.
+    pub extended_privileges: Vec<String>,
+    pub created_by_account: String,
+    pub create_by_user: String,
+}
+
+impl FMComponentPrivilegeSet {
+    pub fn new() -> Self {
+        Self {
+            set_name: String::new(),
+            set_description: String::new(),
+            full_access: false,
+            records: HashMap::new(),
+            layouts: HashMap::new(),
+            value_lists: HashMap::new(),
+            scripts: HashMap::new(),
+            extended_privileges: vec![],
+            created_by_account: String::new(),
+            create_by_user: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FMComponentExtendedPrivilege {
+    pub keyword: String,
+    pub description: String,
+    /// Ids of the privilege sets this privilege is enabled for.
+    pub privilege_sets: Vec<usize>,
+}
+
+impl FMComponentExtendedPrivilege {
+    pub fn new() -> Self {
+        Self {
+            keyword: String::new(),
+            description: String::new(),
+            privilege_sets: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FMComponentCustomFunction {
+    pub name: String,
+    /// Parameter names in call order. The body refers to them like `Let` variables.
+    pub parameters: Vec<String>,
+    /// `None` if the body is missing or could not be decoded, see `body_error`.
+    pub body: Option<Calc>,
+    pub body_error: Option<String>,
+    pub availability: Availability,
+    pub created_by_account: String,
+    pub create_by_user: String,
+}
+
+impl FMComponentCustomFunction {
+    pub fn new() -> Self {
+        Self {
+            name: String::new(),
+            parameters: vec![],
+            body: None,
+            body_error: None,
+            availability: Availability::default(),
+            created_by_account: String::new(),
+            create_by_user: String::new(),
+        }
+    }
+
+    /// Whether the body calls this function itself, `id` being its own id.
+    pub fn is_recursive(&self, id: usize) -> bool {
+        self.body.as_ref().is_some_and(|body| body.custom_functions().contains(&id))
+    }
+}
+
+/// An entry of the External Data Sources list.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FMComponentDataSource {
+    pub source_name: String,
+    pub source_type: DataSourceType,
+    /// Paths tried in order when opening the source.
+    pub paths: Vec<DataSourcePath>,
+    pub created_by_account: String,
+    pub create_by_user: String,
+}
+
+impl FMComponentDataSource {
+    pub fn new() -> Self {
+        Self {
+            source_name: String::new(),
+            source_type: DataSourceType::default(),
+            paths: vec![],
             created_by_account: String::new(),
             create_by_user: String::new(),
         }
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+/// Who may use a custom function in their own calculations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Availability {
+    #[default]
+    AllAccounts,
+    FullAccessOnly,
+    Unknown(u8),
+}
+
+impl From<u8> for Availability {
+    fn from(byte: u8) -> Self {
+        match byte {
+            0 => Availability::AllAccounts,
+            1 => Availability::FullAccessOnly,
+            n => Availability::Unknown(n),
+        }
+    }
+}
+
+/* Parameter names are a single string with one name per line, in call order. */
+pub fn decode_parameters(text: &str) -> Vec<String> {
+    text.split(['\r', '\n'])
+        .filter(|p| !p.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::repr::custom_function::*;
+
+    #[test]
+    fn custom_function_decoding() {
+        assert_eq!(decode_parameters("text\rsearch\r"), ["text", "search"]);
+        assert!(decode_parameters("").is_empty());
+        assert_eq!(Availability::from(1), Availability::FullAccessOnly);
+    }
+}
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DataSourceType {
+    #[default]
+    FileMaker,
+    Odbc,
+    Unknown(u8),
+}
+
+impl From<u8> for DataSourceType {
+    fn from(byte: u8) -> Self {
+        match byte {
+            0 => DataSourceType::FileMaker,
+            1 => DataSourceType::Odbc,
+            n => DataSourceType::Unknown(n),
+        }
+    }
+}
+
+/// The `file:`, `fmnet:` and platform specific prefixes of a FileMaker path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PathPrefix {
+    #[default]
+    File,
+    FileMac,
+    FileWin,
+    FileLinux,
+    FmNet,
+    /// No recognised prefix, such as the DSN of an ODBC source.
+    None,
+}
+
+/// One line of a data source's path list.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataSourcePath {
+    pub prefix: PathPrefix,
+    /// The path with its prefix removed, e.g. `/server/Invoices` for `fmnet:/server/Invoices`.
+    pub path: String,
+}
+
+impl DataSourcePath {
+    /// The name of the file the path points at, without directories or the `.fmp12` extension.
+    pub fn file_name(&self) -> &str {
+        let name = self.path.rsplit(['/', '\\']).next().unwrap_or(&self.path);
+        name.strip_suffix(".fmp12").unwrap_or(name)
+    }
+}
+
+/// Where a table occurrence's base table is defined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BaseTable {
+    Local(u16),
+    /// A table of another file, by data source id and the table's id in that file.
+    External { data_source: u16, table: u16 },
+}
+
+pub fn decode_path(line: &str) -> DataSourcePath {
+    let (prefix, path) = match line.split_once(':') {
+        Some(("file", path)) => (PathPrefix::File, path),
+        Some(("filemac", path)) => (PathPrefix::FileMac, path),
+        Some(("filewin", path)) => (PathPrefix::FileWin, path),
+        Some(("filelinux", path)) => (PathPrefix::FileLinux, path),
+        Some(("fmnet", path)) => (PathPrefix::FmNet, path),
+        _ => (PathPrefix::None, line),
+    };
+    DataSourcePath { prefix, path: path.to_string() }
+}
+
+/* Paths are a single string with one path per line, tried in order. */
+pub fn decode_paths(text: &str) -> Vec<DataSourcePath> {
+    text.split(['\r', '\n'])
+        .filter(|p| !p.is_empty())
+        .map(decode_path)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::repr::data_source::*;
+
+    #[test]
+    fn data_source_paths() {
+        let paths = decode_paths("file:Invoices\rfmnet:/10.0.0.1/Invoices.fmp12\rSalesDSN");
+        assert_eq!(paths.len(), 3);
+        assert_eq!(paths[0], DataSourcePath { prefix: PathPrefix::File, path: "Invoices".to_string() });
+        assert_eq!((paths[1].prefix, paths[1].file_name()), (PathPrefix::FmNet, "Invoices"));
+        assert_eq!((paths[2].prefix, paths[2].path.as_str()), (PathPrefix::None, "SalesDSN"));
+    }
+}
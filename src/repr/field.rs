@@ -0,0 +1,502 @@
+use serde::{Deserialize, Serialize};
+use crate::calc_engine::calc::Calc;
+use crate::repr::component::FMComponentField;
+use crate::util::format_decode::get_int;
+
+/// Byte 0 of a field's type switches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FieldKind {
+    #[default]
+    Simple,
+    Calculation,
+    Summary,
+    Unknown(u8),
+}
+
+impl From<u8> for FieldKind {
+    fn from(byte: u8) -> Self {
+        match byte {
+            0 => FieldKind::Simple,
+            2 => FieldKind::Calculation,
+            3 => FieldKind::Summary,
+            n => FieldKind::Unknown(n),
+        }
+    }
+}
+
+/// Byte 1 of a field's type switches, for simple and calculation fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DataType {
+    #[default]
+    Text,
+    Number,
+    Date,
+    Time,
+    Timestamp,
+    Container,
+    Unknown(u8),
+}
+
+impl From<u8> for DataType {
+    fn from(byte: u8) -> Self {
+        match byte {
+            1 => DataType::Text,
+            2 => DataType::Number,
+            3 => DataType::Date,
+            4 => DataType::Time,
+            5 => DataType::Timestamp,
+            6 => DataType::Container,
+            n => DataType::Unknown(n),
+        }
+    }
+}
+
+/// Auto-enter preset from byte 4, only in effect when byte 11 has its low bit set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AutoEnterPreset {
+    CreationDate,
+    CreationTime,
+    CreationTimestamp,
+    CreationName,
+    CreationAccountName,
+    ModificationDate,
+    ModificationTime,
+    ModificationTimestamp,
+    ModificationName,
+    ModificationAccountName,
+    Unknown(u8),
+}
+
+impl From<u8> for AutoEnterPreset {
+    fn from(byte: u8) -> Self {
+        match byte {
+            0 => AutoEnterPreset::CreationDate,
+            1 => AutoEnterPreset::CreationTime,
+            2 => AutoEnterPreset::CreationTimestamp,
+            3 => AutoEnterPreset::CreationName,
+            4 => AutoEnterPreset::CreationAccountName,
+            5 => AutoEnterPreset::ModificationDate,
+            6 => AutoEnterPreset::ModificationTime,
+            7 => AutoEnterPreset::ModificationTimestamp,
+            8 => AutoEnterPreset::ModificationName,
+            9 => AutoEnterPreset::ModificationAccountName,
+            n => AutoEnterPreset::Unknown(n),
+        }
+    }
+}
+
+/// Index language from byte 7.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum IndexLanguage {
+    Unicode,
+    #[default]
+    Default,
+    Catalan,
+    Croatian,
+    Czech,
+    Danish,
+    Dutch,
+    English,
+    Finnish,
+    FinnishVW,
+    French,
+    German,
+    GermanUmlautAsA,
+    Greek,
+    Hungarian,
+    Icelandic,
+    Italian,
+    Japanese,
+    Norwegian,
+    Polish,
+    Portuguese,
+    Romanian,
+    Russian,
+    Slovak,
+    Slovenian,
+    SpanishModern,
+    Spanish,
+    Swedish,
+    SwedishVW,
+    Turkish,
+    Ukrainian,
+    ChinesePinyin,
+    ChineseStroke,
+    Hebrew,
+    Hindi,
+    Arabic,
+    Estonian,
+    Lithuanian,
+    Latvian,
+    SerbianLatin,
+    Farsi,
+    Bulgarian,
+    Vietnamese,
+    Thai,
+    GreekMixed,
+    Bengali,
+    Telugu,
+    Marathi,
+    Tamil,
+    Gujarati,
+    Kannada,
+    Malayalam,
+    Panjabi,
+    Korean,
+    Unknown(u8),
+}
+
+impl From<u8> for IndexLanguage {
+    fn from(byte: u8) -> Self {
+        match byte {
+            2 => IndexLanguage::Unicode,
+            3 => IndexLanguage::Default,
+            16 => IndexLanguage::Catalan,
+            17 => IndexLanguage::Croatian,
+            18 => IndexLanguage::Czech,
+            19 => IndexLanguage::Danish,
+            20 => IndexLanguage::Dutch,
+            21 => IndexLanguage::English,
+            22 => IndexLanguage::Finnish,
+            23 => IndexLanguage::FinnishVW,
+            24 => IndexLanguage::French,
+            25 => IndexLanguage::German,
+            26 => IndexLanguage::GermanUmlautAsA,
+            27 => IndexLanguage::Greek,
+            28 => IndexLanguage::Hungarian,
+            29 => IndexLanguage::Icelandic,
+            30 => IndexLanguage::Italian,
+            31 => IndexLanguage::Japanese,
+            32 => IndexLanguage::Norwegian,
+            33 => IndexLanguage::Polish,
+            34 => IndexLanguage::Portuguese,
+            35 => IndexLanguage::Romanian,
+            36 => IndexLanguage::Russian,
+            37 => IndexLanguage::Slovak,
+            38 => IndexLanguage::Slovenian,
+            39 => IndexLanguage::SpanishModern,
+            40 => IndexLanguage::Spanish,
+            41 => IndexLanguage::Swedish,
+            42 => IndexLanguage::SwedishVW,
+            43 => IndexLanguage::Turkish,
+            44 => IndexLanguage::Ukrainian,
+            45 => IndexLanguage::ChinesePinyin,
+            46 => IndexLanguage::ChineseStroke,
+            47 => IndexLanguage::Hebrew,
+            48 => IndexLanguage::Hindi,
+            49 => IndexLanguage::Arabic,
+            50 => IndexLanguage::Estonian,
+            51 => IndexLanguage::Lithuanian,
+            52 => IndexLanguage::Latvian,
+            53 => IndexLanguage::SerbianLatin,
+            54 => IndexLanguage::Farsi,
+            55 => IndexLanguage::Bulgarian,
+            56 => IndexLanguage::Vietnamese,
+            57 => IndexLanguage::Thai,
+            58 => IndexLanguage::GreekMixed,
+            59 => IndexLanguage::Bengali,
+            60 => IndexLanguage::Telugu,
+            61 => IndexLanguage::Marathi,
+            62 => IndexLanguage::Tamil,
+            63 => IndexLanguage::Gujarati,
+            64 => IndexLanguage::Kannada,
+            65 => IndexLanguage::Malayalam,
+            67 => IndexLanguage::Panjabi,
+            76 => IndexLanguage::Korean,
+            n => IndexLanguage::Unknown(n),
+        }
+    }
+}
+
+/// Byte 9 of a field's type switches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Storage {
+    #[default]
+    Stored,
+    Unstored,
+    Global,
+}
+
+impl From<u8> for Storage {
+    fn from(byte: u8) -> Self {
+        if byte & 0x01 != 0 {
+            Storage::Global
+        } else if byte & 0x02 != 0 {
+            Storage::Unstored
+        } else {
+            Storage::Stored
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SummaryOp {
+    #[default]
+    Total,
+    Average,
+    Count,
+    Minimum,
+    Maximum,
+    StandardDeviation,
+    FractionOfTotal,
+    List,
+    Unknown(u8),
+}
+
+impl From<u8> for SummaryOp {
+    fn from(byte: u8) -> Self {
+        match byte {
+            0 => SummaryOp::Total,
+            1 => SummaryOp::Average,
+            2 => SummaryOp::Count,
+            3 => SummaryOp::Minimum,
+            4 => SummaryOp::Maximum,
+            5 => SummaryOp::StandardDeviation,
+            6 => SummaryOp::FractionOfTotal,
+            7 => SummaryOp::List,
+            n => SummaryOp::Unknown(n),
+        }
+    }
+}
+
+/// What a summary field summarises, and how.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldSummary {
+    pub operation: SummaryOp,
+    /// Id of the summarised field, in the same table.
+    pub field: u16,
+    pub field_name: String,
+}
+
+/* Summary definition at key 8: the operation, then the summarised field id. */
+pub fn decode_field_summary(data: &[u8]) -> FieldSummary {
+    FieldSummary {
+        operation: SummaryOp::from(data.first().copied().unwrap_or(0)),
+        field: data.get(1..3).map_or(0, |id| get_int(id) as u16),
+        field_name: String::new(),
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SerialNumber {
+    pub next_value: String,
+    pub increment: String,
+    /// Assigned when the record is committed rather than when it is created.
+    pub on_commit: bool,
+}
+
+/// Source of a looked up value, as a table occurrence and field id.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lookup {
+    pub table_occurrence: u16,
+    pub field: u16,
+    pub source_name: String,
+}
+
+/// Auto-enter options, from bytes 4, 10 and 11 of the type switches and the keys they enable.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AutoEnter {
+    pub preset: Option<AutoEnterPreset>,
+    pub serial: Option<SerialNumber>,
+    pub data: Option<String>,
+    /// `None` if the calculation is off, missing or could not be decoded, see `calculation_error`.
+    pub calculation: Option<Calc>,
+    pub calculation_error: Option<String>,
+    pub do_not_replace_existing: bool,
+    pub evaluate_if_all_empty: bool,
+    pub last_visited_value: bool,
+    pub lookup: Option<Lookup>,
+    pub prohibit_modification: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StrictType {
+    Numeric,
+    FourDigitYear,
+    TimeOfDay,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationRange {
+    pub from: String,
+    pub to: String,
+}
+
+/// Validation options, from bytes 14 and 15 of the type switches and the keys they enable.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Validation {
+    /// Validate on every change, not only during data entry.
+    pub always: bool,
+    pub user_can_override: bool,
+    pub not_empty: bool,
+    pub unique: bool,
+    pub existing: bool,
+    pub strict_type: Option<StrictType>,
+    /// Id of the value list values must be a member of.
+    pub value_list: Option<u16>,
+    pub range: Option<ValidationRange>,
+    pub max_characters: Option<u16>,
+    /// `None` if the calculation is off, missing or could not be decoded, see `calculation_error`.
+    pub calculation: Option<Calc>,
+    pub calculation_error: Option<String>,
+    pub error_message: Option<String>,
+}
+
+/* The switch bit decides whether an option is on, what its own key stored is kept while it is. */
+fn enable<T: Default>(option: &mut Option<T>, enabled: bool) {
+    if !enabled {
+        *option = None;
+    } else if option.is_none() {
+        *option = Some(T::default());
+    }
+}
+
+/* Calculations only come from their own key, the switch bit can only turn them off. */
+fn enable_calculation(calculation: &mut Option<Calc>, error: &mut Option<String>, enabled: bool) {
+    if !enabled {
+        *calculation = None;
+        *error = None;
+    }
+}
+
+/// Fill in a field's kind, data type and options from the switches stored at key 2,
+/// keeping the serial numbers, lookups, ranges and messages read from other keys
+/// for the options the switches turn on. Call it once all of the field's keys are read.
+pub fn decode_field_switches(field: &mut FMComponentField, switches: &[u8]) {
+    let byte = |i: usize| switches.get(i).copied().unwrap_or(0);
+
+    field.kind = FieldKind::from(byte(0));
+    /* Summary fields use byte 1 for their operation, only "List of" gives text. */
+    field.data_type = match field.kind {
+        FieldKind::Summary if byte(1) == 1 => DataType::Text,
+        FieldKind::Summary => DataType::Number,
+        _ => DataType::from(byte(1)),
+    };
+
+    let auto_enter = &mut field.auto_enter;
+    auto_enter.preset = (byte(11) & 0x01 != 0).then(|| AutoEnterPreset::from(byte(4)));
+    enable(&mut auto_enter.serial, byte(11) & 0x02 != 0 || byte(10) & 0x02 != 0);
+    if let Some(serial) = auto_enter.serial.as_mut() {
+        serial.on_commit = byte(10) & 0x02 != 0;
+    }
+    enable(&mut auto_enter.data, byte(11) & 0x04 != 0);
+    enable_calculation(&mut auto_enter.calculation, &mut auto_enter.calculation_error, byte(11) & 0x08 != 0);
+    /* 128 marks a lookup together with byte 10 = 4, otherwise a replacing calculation. */
+    auto_enter.do_not_replace_existing = byte(11) & 0x08 != 0 && byte(11) & 0x80 == 0;
+    auto_enter.evaluate_if_all_empty = byte(11) & 0x20 != 0;
+    auto_enter.last_visited_value = byte(11) & 0x10 != 0;
+    enable(&mut auto_enter.lookup, byte(10) & 0x04 != 0 && byte(11) & 0x80 != 0);
+    auto_enter.prohibit_modification = byte(10) & 0x01 != 0;
+
+    let validation = &mut field.validation;
+    validation.always = byte(14) & 0x04 != 0;
+    validation.user_can_override = byte(15) & 0x04 == 0;
+    validation.not_empty = byte(15) & 0x08 != 0;
+    validation.unique = byte(15) & 0x10 != 0;
+    validation.existing = byte(15) & 0x20 != 0;
+    validation.strict_type = match byte(14) {
+        b if b & 0x10 != 0 => Some(StrictType::Numeric),
+        b if b & 0x20 != 0 => Some(StrictType::FourDigitYear),
+        b if b & 0x40 != 0 => Some(StrictType::TimeOfDay),
+        _ => None,
+    };
+    enable(&mut validation.value_list, byte(14) & 0x01 != 0);
+    enable(&mut validation.range, byte(15) & 0x40 != 0);
+    enable(&mut validation.max_characters, byte(14) & 0x02 != 0);
+    enable_calculation(&mut validation.calculation, &mut validation.calculation_error, byte(15) & 0x01 != 0);
+    enable(&mut validation.error_message, byte(15) & 0x80 != 0);
+    field.language = IndexLanguage::from(byte(7));
+    field.storage = Storage::from(byte(9));
+    field.repetitions = byte(25).max(1) as u16;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::repr::component::FMComponentField;
+    use crate::repr::field::*;
+
+    #[test]
+    fn field_switches() {
+        let mut field = FMComponentField::new();
+        decode_field_switches(&mut field, &[0, 2, 0, 0, 5, 0, 0, 21, 0, 0, 0, 1]);
+        assert_eq!(field.kind, FieldKind::Simple);
+        assert_eq!(field.data_type, DataType::Number);
+        assert_eq!(field.auto_enter.preset, Some(AutoEnterPreset::ModificationDate));
+        assert_eq!(field.language, IndexLanguage::English);
+
+        decode_field_switches(&mut field, &[2, 5, 0, 0, 0, 0, 0, 3, 0, 8, 0, 0]);
+        assert_eq!(field.kind, FieldKind::Calculation);
+        assert_eq!(field.data_type, DataType::Timestamp);
+        assert_eq!(field.auto_enter, AutoEnter::default());
+        assert_eq!(field.language, IndexLanguage::Default);
+
+        assert_eq!(field.storage, Storage::Stored);
+        assert_eq!(field.repetitions, 1);
+
+        let mut switches = [0u8; 26];
+        switches[9] = 10;
+        switches[25] = 3;
+        decode_field_switches(&mut field, &switches);
+        assert_eq!(field.storage, Storage::Unstored);
+        assert_eq!(field.repetitions, 3);
+
+        decode_field_switches(&mut field, &[3, 1]);
+        assert_eq!(field.data_type, DataType::Text);
+        assert_eq!(field.language, IndexLanguage::Unknown(0));
+    }
+
+    #[test]
+    fn field_options() {
+        let mut field = FMComponentField::new();
+        field.auto_enter.calculation = Some(Calc::Variable("$$next".to_string()));
+        let mut switches = [0u8; 16];
+        switches[10] = 0x02;
+        switches[11] = 0x08 | 0x20;
+        switches[14] = 0x01 | 0x04 | 0x10;
+        switches[15] = 0x08 | 0x10 | 0x04 | 0x01;
+        decode_field_switches(&mut field, &switches);
+        assert!(field.auto_enter.serial.as_ref().is_some_and(|s| s.on_commit));
+        assert_eq!(field.auto_enter.calculation, Some(Calc::Variable("$$next".to_string())));
+        assert!(field.auto_enter.do_not_replace_existing);
+        assert!(field.auto_enter.evaluate_if_all_empty);
+        assert_eq!(field.auto_enter.lookup, None);
+        assert!(field.validation.always && field.validation.not_empty && field.validation.unique);
+        assert!(!field.validation.user_can_override);
+        assert_eq!(field.validation.strict_type, Some(StrictType::Numeric));
+        assert_eq!(field.validation.value_list, Some(0));
+        assert_eq!(field.validation.range, None);
+
+        switches[10] = 0x04;
+        switches[11] = 0x80 | 0x08;
+        decode_field_switches(&mut field, &switches);
+        assert_eq!(field.auto_enter.lookup, Some(Lookup::default()));
+        assert!(!field.auto_enter.do_not_replace_existing);
+
+        /* Values read from the option keys are kept for the options that stay on */
+        field.auto_enter.lookup.as_mut().unwrap().field = 7;
+        field.validation.max_characters = Some(20);
+        field.validation.error_message = Some("Required".to_string());
+        switches[11] = 0x80;
+        switches[14] |= 0x02;
+        switches[15] |= 0x80;
+        decode_field_switches(&mut field, &switches);
+        assert_eq!(field.auto_enter.lookup.as_ref().map(|l| l.field), Some(7));
+        assert_eq!(field.auto_enter.calculation, None);
+        assert_eq!(field.validation.max_characters, Some(20));
+        assert_eq!(field.validation.error_message.as_deref(), Some("Required"));
+        assert!(field.validation.unique);
+
+        /* A cleared bit turns the option off whatever its key stored */
+        switches[15] &= !0x80;
+        decode_field_switches(&mut field, &switches);
+        assert_eq!(field.validation.error_message, None);
+    }
+
+    #[test]
+    fn field_summary() {
+        let summary = decode_field_summary(&[7, 0, 12]);
+        assert_eq!(summary.operation, SummaryOp::List);
+        assert_eq!(summary.field, 12);
+        assert_eq!(decode_field_summary(&[]).operation, SummaryOp::Total);
+    }
+}
@@ -1,30 +1,158 @@
+use crate::calc_engine::calc::{Calc, FieldRef};
+use crate::error::FmpError;
 use crate::repr::component;
+use crate::fmp_format::header::FileHeader;
+use crate::repr::graph::RelationshipGraph;
+use crate::repr::record::{RecordSource, Records};
+use crate::repr::script_tree::ScriptTreeItem;
+use crate::repr::trigger::Trigger;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct FmpFile {
     pub name: String,
+    pub header: FileHeader,
     pub tables: HashMap<usize, component::FMComponentTable>,
     pub relationships: HashMap<usize, component::FMComponentRelationship>,
     pub layouts: HashMap<usize, component::FMComponentLayout>,
     pub value_lists: HashMap<usize, component::FMComponentValueList>,
+    pub custom_functions: HashMap<usize, component::FMComponentCustomFunction>,
     pub scripts: HashMap<usize, component::FMComponentScript>,
+    /// Scripts, folders and separators in Script Workspace order.
+    pub script_tree: Vec<ScriptTreeItem>,
     pub table_occurrences: HashMap<usize, component::FMComponentTableOccurence>,
-    pub tests: Vec<component::FMComponentTest>,
+    pub data_sources: HashMap<usize, component::FMComponentDataSource>,
+    /// Every script trigger and button action in the file, with the script each one runs.
+    pub triggers: Vec<Trigger>,
+    pub accounts: HashMap<usize, component::FMComponentAccount>,
+    pub privilege_sets: HashMap<usize, component::FMComponentPrivilegeSet>,
+    pub extended_privileges: HashMap<usize, component::FMComponentExtendedPrivilege>,
+    /* Not decoded yet, kept out of the public API until they are. */
+    pub(crate) tests: Vec<component::FMComponentTest>,
+    /// Problems that did not stop decompiling, the data they concern was skipped.
+    #[serde(skip)]
+    pub diagnostics: Vec<FmpError>,
+    #[serde(skip)]
+    pub(crate) source: RecordSource,
 }
 
 impl FmpFile {
     pub fn new() -> Self {
         Self {
             name: String::new(),
+            header: FileHeader::default(),
             tables: HashMap::new(),
             relationships: HashMap::new(),
             layouts: HashMap::new(),
             value_lists: HashMap::new(),
+            custom_functions: HashMap::new(),
             scripts: HashMap::new(),
+            script_tree: vec![],
             table_occurrences: HashMap::new(),
+            data_sources: HashMap::new(),
+            triggers: vec![],
+            accounts: HashMap::new(),
+            privilege_sets: HashMap::new(),
+            extended_privileges: HashMap::new(),
             tests: vec![],
+            diagnostics: vec![],
+            source: RecordSource::None,
         }
     }
+
+    /// `Table Occurrence::Field` for a field referenced through a table occurrence,
+    /// or `None` if either id is missing from this file or the base table lives in another file.
+    pub fn field_reference_name(&self, table_occurrence: usize, field: usize) -> Option<String> {
+        let occurrence = self.table_occurrences.get(&table_occurrence)?;
+        if occurrence.data_source.is_some() {
+            return None;
+        }
+        let field = self.tables
+            .get(&(occurrence.table_actual as usize))?
+            .fields
+            .get(&u16::try_from(field).ok()?)?;
+        Some(format!("{}::{}", occurrence.table_occurence_name, field.field_name))
+    }
+
+    /// The records of the table with id `table`, values typed by their field's data type.
+    /// Record data is not kept by the decompiler, the file this one was decompiled from is
+    /// reopened and its sectors are read as the records are iterated. Fails if it was decompiled
+    /// from a reader, or the file changed since.
+    pub fn records(&self, table: usize) -> Result<Records<'_>, FmpError> {
+        Records::new(&self.source, &self.header, table, self.tables.get(&table))
+    }
+
+    /// Name the field references this file could not resolve on its own, such as fields of
+    /// tables in other files, throughout its calculations, script steps and schema.
+    pub fn resolve_external_fields(&mut self, resolve: &dyn Fn(usize, usize) -> Option<String>) {
+        let name = |name: &mut String, occurrence: u16, field: u16| if name.is_empty() {
+            *name = resolve(occurrence as usize, field as usize).unwrap_or_default();
+        };
+        let resolve_ref = |field: &mut FieldRef| if field.name.is_none() {
+            field.name = resolve(field.table_occurrence, field.field);
+        };
+        let resolve_calc = |calc: &mut Calc| calc.walk_mut(&mut |calc| {
+            if let Calc::Field(field) = calc {
+                resolve_ref(field);
+            }
+        });
+        let resolve_trigger = |trigger: &mut Trigger| {
+            if let Some(Ok(calc)) = trigger.parameter_calc.as_mut() {
+                resolve_calc(calc);
+            }
+        };
+
+        for field in self.tables.values_mut().flat_map(|t| t.fields.values_mut()) {
+            field.calculation.iter_mut()
+                .chain(field.auto_enter.calculation.iter_mut())
+                .chain(field.validation.calculation.iter_mut())
+                .for_each(resolve_calc);
+        }
+        for function in self.custom_functions.values_mut() {
+            function.body.iter_mut().for_each(resolve_calc);
+        }
+        for step in self.scripts.values_mut().flat_map(|s| s.instructions.values_mut()) {
+            step.params.calcs_mut().into_iter().for_each(resolve_calc);
+            step.params.fields_mut().into_iter().for_each(resolve_ref);
+        }
+        self.triggers.iter_mut().for_each(resolve_trigger);
+        for layout in self.layouts.values_mut() {
+            layout.triggers.iter_mut().for_each(resolve_trigger);
+            for object in layout.objects.iter_mut() {
+                object.walk_mut(&mut |o| {
+                    if let Some(field) = o.field.as_mut() {
+                        name(&mut field.field_name, field.table_occurrence, field.field);
+                    }
+                    o.triggers.iter_mut().for_each(resolve_trigger);
+                });
+            }
+        }
+        for r in self.relationships.values_mut() {
+            if let Some(table1) = r.table1 {
+                for p in r.predicates.iter_mut() {
+                    name(&mut p.field1_name, table1, p.field1);
+                }
+                for s in r.table1_options.sort.iter_mut() {
+                    name(&mut s.field_name, table1, s.field);
+                }
+            }
+            for p in r.predicates.iter_mut() {
+                name(&mut p.field2_name, r.table2, p.field2);
+            }
+            for s in r.table2_options.sort.iter_mut() {
+                name(&mut s.field_name, r.table2, s.field);
+            }
+        }
+        for list in self.value_lists.values_mut() {
+            for field in list.primary_field.iter_mut().chain(list.secondary_field.iter_mut()) {
+                name(&mut field.field_name, field.table_occurrence, field.field);
+            }
+        }
+    }
+
+    /// The relationship graph, ready to render as DOT or Mermaid.
+    pub fn relationship_graph(&self) -> RelationshipGraph {
+        RelationshipGraph::new(self)
+    }
 }
@@ -0,0 +1,228 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+use serde::{Deserialize, Serialize};
+
+use crate::repr::file::FmpFile;
+
+/* Fill colours handed out to base tables in order of their ids. */
+const PALETTE : [&str; 8] = [
+    "#aec7e8", "#ffbb78", "#98df8a", "#ff9896",
+    "#c5b0d5", "#c49c94", "#f7b6d2", "#dbdb8d",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphNode {
+    /// Table occurrence id.
+    pub id: usize,
+    pub name: String,
+    pub base_table: usize,
+    pub base_table_name: String,
+    /// Data source of a base table in another file.
+    pub data_source: Option<u16>,
+    pub data_source_name: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub from: usize,
+    pub to: usize,
+    /// One `field comparison field` line per predicate.
+    pub predicates: Vec<String>,
+}
+
+/// Table occurrences and the relationships between them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelationshipGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/* Local tables sort before those of other files. */
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct GroupKey(Option<u16>, usize);
+
+impl fmt::Display for GroupKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(source) => write!(f, "{}_{}", source, self.1),
+            None => write!(f, "{}", self.1),
+        }
+    }
+}
+
+fn field_label(name: &str, id: u16) -> String {
+    if name.is_empty() { format!("#{}", id) } else { name.to_string() }
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn mermaid_escape(s: &str) -> String {
+    s.replace('"', "#quot;")
+}
+
+impl RelationshipGraph {
+    pub fn new(file: &FmpFile) -> Self {
+        let mut nodes = file.table_occurrences.iter()
+            .map(|(id, occurrence)| GraphNode {
+                id: *id,
+                name: occurrence.table_occurence_name.clone(),
+                base_table: occurrence.table_actual as usize,
+                base_table_name: match occurrence.data_source {
+                    Some(_) => String::new(),
+                    None => file.tables.get(&(occurrence.table_actual as usize))
+                        .map(|t| t.table_name.clone())
+                        .unwrap_or_default(),
+                },
+                data_source: occurrence.data_source,
+                data_source_name: occurrence.data_source_name.clone(),
+            })
+            .collect::<Vec<_>>();
+        nodes.sort_by_key(|n| n.id);
+
+        let mut relationships = file.relationships.iter().collect::<Vec<_>>();
+        relationships.sort_by_key(|(id, _)| **id);
+        /* Relationships whose first occurrence is unknown have nothing to start from */
+        let edges = relationships.into_iter()
+            .filter_map(|(_, r)| Some(GraphEdge {
+                from: r.table1? as usize,
+                to: r.table2 as usize,
+                predicates: r.predicates.iter()
+                    .map(|p| format!("{} {} {}",
+                                     field_label(&p.field1_name, p.field1),
+                                     p.comparison,
+                                     field_label(&p.field2_name, p.field2)))
+                    .collect(),
+            }))
+            .collect();
+        Self { nodes, edges }
+    }
+
+    /* Occurrences grouped by base table, each table with its own colour.
+     * Tables of other files are keyed by data source and labelled with its name. */
+    fn groups(&self) -> BTreeMap<GroupKey, (String, &'static str, Vec<&GraphNode>)> {
+        let mut groups = BTreeMap::<GroupKey, (String, &'static str, Vec<&GraphNode>)>::new();
+        for node in &self.nodes {
+            groups.entry(GroupKey(node.data_source, node.base_table))
+                .or_insert_with(|| match (node.base_table_name.as_str(), node.data_source) {
+                    ("", Some(source)) if node.data_source_name.is_empty() => {
+                        (format!("Table {} in data source {}", node.base_table, source), "", vec![])
+                    },
+                    ("", Some(_)) => (format!("Table {} in {}", node.base_table, node.data_source_name), "", vec![]),
+                    ("", None) => (format!("Table {}", node.base_table), "", vec![]),
+                    (name, _) => (name.to_string(), "", vec![]),
+                })
+                .2.push(node);
+        }
+        for (i, group) in groups.values_mut().enumerate() {
+            group.1 = PALETTE[i % PALETTE.len()];
+        }
+        groups
+    }
+
+    /// Render as a Graphviz DOT graph.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("graph relationships {\n    node [shape=box, style=filled];\n");
+        for (table, (name, colour, nodes)) in self.groups() {
+            let _ = writeln!(out, "    subgraph cluster_{} {{\n        label=\"{}\";", table, dot_escape(&name));
+            for node in nodes {
+                let _ = writeln!(out, "        to_{} [label=\"{}\", fillcolor=\"{}\"];", node.id, dot_escape(&node.name), colour);
+            }
+            out.push_str("    }\n");
+        }
+        for edge in &self.edges {
+            let _ = writeln!(out, "    to_{} -- to_{} [label=\"{}\"];",
+                             edge.from, edge.to, dot_escape(&edge.predicates.join("\n")).replace('\n', "\\n"));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Render as a Mermaid flowchart.
+    pub fn to_mermaid(&self) -> String {
+        let mut out = String::from("graph LR\n");
+        let mut styles = String::new();
+        for (table, (name, colour, nodes)) in self.groups() {
+            let _ = writeln!(out, "    subgraph table_{} [\"{}\"]", table, mermaid_escape(&name));
+            for node in nodes {
+                let _ = writeln!(out, "        to_{}[\"{}\"]", node.id, mermaid_escape(&node.name));
+                let _ = writeln!(styles, "    style to_{} fill:{}", node.id, colour);
+            }
+            out.push_str("    end\n");
+        }
+        for edge in &self.edges {
+            if edge.predicates.is_empty() {
+                let _ = writeln!(out, "    to_{} --- to_{}", edge.from, edge.to);
+            } else {
+                let _ = writeln!(out, "    to_{} ---|\"{}\"| to_{}",
+                                 edge.from, mermaid_escape(&edge.predicates.join("<br/>")), edge.to);
+            }
+        }
+        out.push_str(&styles);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::repr::component::{FMComponentRelationship, FMComponentTable, FMComponentTableOccurence};
+    use crate::repr::file::FmpFile;
+    use crate::repr::relationship::Predicate;
+
+    #[test]
+    fn graph_output() {
+        let mut file = FmpFile::new();
+        for (id, name, table) in [(1, "Invoices", 1), (2, "Lines", 2), (3, "Invoices 2", 1)] {
+            let mut occurrence = FMComponentTableOccurence::new();
+            occurrence.table_occurence_name = name.to_string();
+            occurrence.table_actual = table;
+            file.table_occurrences.insert(id, occurrence);
+        }
+        let mut table = FMComponentTable::new();
+        table.table_name = "Invoices".to_string();
+        file.tables.insert(1, table);
+
+        let mut relationship = FMComponentRelationship::new();
+        relationship.table1 = Some(1);
+        relationship.table2 = 2;
+        relationship.predicates.push(Predicate {
+            field1: 1,
+            field1_name: "Invoices::id".to_string(),
+            field2: 4,
+            ..Predicate::default()
+        });
+        file.relationships.insert(0, relationship);
+
+        let graph = file.relationship_graph();
+        assert_eq!(graph.to_dot(), "\
+graph relationships {
+    node [shape=box, style=filled];
+    subgraph cluster_1 {
+        label=\"Invoices\";
+        to_1 [label=\"Invoices\", fillcolor=\"#aec7e8\"];
+        to_3 [label=\"Invoices 2\", fillcolor=\"#aec7e8\"];
+    }
+    subgraph cluster_2 {
+        label=\"Table 2\";
+        to_2 [label=\"Lines\", fillcolor=\"#ffbb78\"];
+    }
+    to_1 -- to_2 [label=\"Invoices::id = #4\"];
+}
+");
+        assert_eq!(graph.to_mermaid(), "\
+graph LR
+    subgraph table_1 [\"Invoices\"]
+        to_1[\"Invoices\"]
+        to_3[\"Invoices 2\"]
+    end
+    subgraph table_2 [\"Table 2\"]
+        to_2[\"Lines\"]
+    end
+    to_1 ---|\"Invoices::id = #4\"| to_2
+    style to_1 fill:#aec7e8
+    style to_3 fill:#aec7e8
+    style to_2 fill:#ffbb78
+");
+    }
+}
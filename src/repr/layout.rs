@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use crate::repr::component::FMComponentLayout;
+use crate::repr::trigger::Trigger;
+use crate::util::format_decode::get_int;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PartKind {
+    TopNavigation,
+    TitleHeader,
+    Header,
+    LeadingGrandSummary,
+    SubSummaryLeading,
+    #[default]
+    Body,
+    SubSummaryTrailing,
+    TrailingGrandSummary,
+    Footer,
+    TitleFooter,
+    BottomNavigation,
+    Unknown(u8),
+}
+
+impl From<u8> for PartKind {
+    fn from(byte: u8) -> Self {
+        match byte {
+            0 => PartKind::TopNavigation,
+            1 => PartKind::TitleHeader,
+            2 => PartKind::Header,
+            3 => PartKind::LeadingGrandSummary,
+            4 => PartKind::SubSummaryLeading,
+            5 => PartKind::Body,
+            6 => PartKind::SubSummaryTrailing,
+            7 => PartKind::TrailingGrandSummary,
+            8 => PartKind::Footer,
+            9 => PartKind::TitleFooter,
+            10 => PartKind::BottomNavigation,
+            n => PartKind::Unknown(n),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayoutPart {
+    pub kind: PartKind,
+    /// Height in points.
+    pub height: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ObjectKind {
+    #[default]
+    Field,
+    Text,
+    Button,
+    ButtonBar,
+    Portal,
+    TabControl,
+    SlideControl,
+    /// A single tab or slide of a tab or slide control.
+    Panel,
+    Popover,
+    PopoverButton,
+    WebViewer,
+    Group,
+    Rectangle,
+    Line,
+    Oval,
+    Unknown(u8),
+}
+
+impl From<u8> for ObjectKind {
+    fn from(byte: u8) -> Self {
+        match byte {
+            0 => ObjectKind::Field,
+            1 => ObjectKind::Text,
+            2 => ObjectKind::Button,
+            3 => ObjectKind::ButtonBar,
+            4 => ObjectKind::Portal,
+            5 => ObjectKind::TabControl,
+            6 => ObjectKind::SlideControl,
+            7 => ObjectKind::Panel,
+            8 => ObjectKind::Popover,
+            9 => ObjectKind::PopoverButton,
+            10 => ObjectKind::WebViewer,
+            11 => ObjectKind::Group,
+            12 => ObjectKind::Rectangle,
+            13 => ObjectKind::Line,
+            14 => ObjectKind::Oval,
+            n => ObjectKind::Unknown(n),
+        }
+    }
+}
+
+/// Position of an object on the layout, in points from the top left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Bounds {
+    pub top: i16,
+    pub left: i16,
+    pub bottom: i16,
+    pub right: i16,
+}
+
+/// A field shown by a layout object, through a table occurrence.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldTarget {
+    pub table_occurrence: u16,
+    pub field: u16,
+    pub field_name: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LayoutObject {
+    pub id: usize,
+    pub kind: ObjectKind,
+    pub name: String,
+    pub bounds: Bounds,
+    /// Field of a field object.
+    pub field: Option<FieldTarget>,
+    /// Table occurrence of a portal.
+    pub table_occurrence: Option<u16>,
+    pub table_occurrence_name: String,
+    /// Text of a text object or label of a button.
+    pub text: Option<String>,
+    pub triggers: Vec<Trigger>,
+    /// Objects inside button bars, portals, panels, popovers and groups.
+    pub children: Vec<LayoutObject>,
+}
+
+impl LayoutObject {
+    /// Visit this object and every object inside it.
+    pub fn walk_mut(&mut self, visit: &mut dyn FnMut(&mut LayoutObject)) {
+        visit(self);
+        for child in self.children.iter_mut() {
+            child.walk_mut(visit);
+        }
+    }
+}
+
+/* Layout switches at key 2 start with the layout's table occurrence, 2 bytes. */
+pub fn decode_layout_switches(layout: &mut FMComponentLayout, data: &[u8]) {
+    layout.table_occurrence = data.get(0..2).map_or(0, get_int) as u16;
+}
+
+/* Part switches at key 2: the kind, then the height. */
+pub fn decode_part(data: &[u8]) -> LayoutPart {
+    LayoutPart {
+        kind: PartKind::from(data.first().copied().unwrap_or(5)),
+        height: data.get(1..3).map_or(0, get_int) as u16,
+    }
+}
+
+/* Object switches at key 2: the kind, then top, left, bottom and right as 2 byte integers. */
+pub fn decode_object_switches(object: &mut LayoutObject, data: &[u8]) {
+    let coordinate = |i: usize| data.get(1 + i * 2..3 + i * 2).map_or(0, get_int) as u16 as i16;
+    object.kind = ObjectKind::from(data.first().copied().unwrap_or(0));
+    object.bounds = Bounds {
+        top: coordinate(0),
+        left: coordinate(1),
+        bottom: coordinate(2),
+        right: coordinate(3),
+    };
+}
+
+/// Nest objects keyed by their object path under their parents, ordered by id.
+pub fn build_object_tree(mut objects: BTreeMap<Vec<usize>, LayoutObject>) -> Vec<LayoutObject> {
+    let mut paths = objects.keys().cloned().collect::<Vec<_>>();
+    /* Deepest objects first, so every child is complete before it is moved. */
+    paths.sort_by_key(|path| std::cmp::Reverse(path.len()));
+    for path in paths {
+        if path.len() < 2 {
+            continue;
+        }
+        let Some(child) = objects.remove(&path) else { continue };
+        let parent = objects.entry(path[..path.len() - 1].to_vec()).or_insert_with(|| LayoutObject {
+            id: path[path.len() - 2],
+            ..LayoutObject::default()
+        });
+        let at = parent.children.partition_point(|c| c.id < child.id);
+        parent.children.insert(at, child);
+    }
+    objects.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use crate::repr::layout::*;
+
+    fn object(id: usize) -> LayoutObject {
+        LayoutObject { id, ..LayoutObject::default() }
+    }
+
+    #[test]
+    fn layout_decoding() {
+        let mut layout = FMComponentLayout::new();
+        decode_layout_switches(&mut layout, &[0, 3, 1]);
+        assert_eq!(layout.table_occurrence, 3);
+
+        let part = decode_part(&[2, 0, 60]);
+        assert_eq!((part.kind, part.height), (PartKind::Header, 60));
+
+        let mut field = object(1);
+        decode_object_switches(&mut field, &[4, 0, 10, 0, 20, 0, 110, 1, 44]);
+        assert_eq!(field.kind, ObjectKind::Portal);
+        assert_eq!(field.bounds, Bounds { top: 10, left: 20, bottom: 110, right: 300 });
+
+        let mut flat = BTreeMap::new();
+        flat.insert(vec![2], object(2));
+        flat.insert(vec![1, 5, 6], object(6));
+        flat.insert(vec![1], object(1));
+        flat.insert(vec![1, 5], object(5));
+        flat.insert(vec![1, 3], object(3));
+        let tree = build_object_tree(flat);
+        assert_eq!(tree.iter().map(|o| o.id).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(tree[0].children.iter().map(|o| o.id).collect::<Vec<_>>(), vec![3, 5]);
+        assert_eq!(tree[0].children[1].children[0].id, 6);
+    }
+}
@@ -1,2 +1,14 @@
 pub mod component;
+pub mod custom_function;
+pub mod data_source;
+pub mod field;
 pub mod file;
+pub mod graph;
+pub mod record;
+pub mod layout;
+pub mod relationship;
+pub mod script_tree;
+pub mod security;
+pub mod solution;
+pub mod trigger;
+pub mod value_list;
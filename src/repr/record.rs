@@ -0,0 +1,444 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Cursor, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+
+use crate::calc_engine::number::Decimal;
+use crate::decompile::decompiler::{read_fmp12_header, read_sector};
+use crate::error::FmpError;
+use crate::fmp_format::chunk::get_chunk_from_code;
+use crate::fmp_format::header::FileHeader;
+use crate::fmp_format::sector::{self, SECTOR_SIZE};
+use crate::repr::component::FMComponentTable;
+use crate::repr::field::DataType;
+use crate::util::format_decode::{fm_string_decrypt, get_int};
+
+/// Stored values of one record, by field id and then repetition, starting at 1.
+type RawRecord = BTreeMap<u16, BTreeMap<usize, Vec<u8>>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub struct Date {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub struct Time {
+    pub hours: u32,
+    pub minutes: u8,
+    pub seconds: u8,
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}:{:02}", self.hours, self.minutes, self.seconds)
+    }
+}
+
+/// A reference to a container's contents. Embedded data itself is not extracted.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ContainerRef {
+    /// The stored reference, e.g. `image:/Photos/logo.png` or `remote:Invoice.pdf`.
+    pub reference: String,
+}
+
+impl ContainerRef {
+    /// The file name at the end of the reference.
+    pub fn file_name(&self) -> &str {
+        let path = self.reference.split_once(':').map_or(self.reference.as_str(), |(_, path)| path);
+        path.rsplit(['/', '\\']).next().unwrap_or(path)
+    }
+}
+
+/// A field value, typed by the field's data type.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Value {
+    #[default]
+    Empty,
+    Text(String),
+    Number(Decimal),
+    Date(Date),
+    Time(Time),
+    Timestamp(Date, Time),
+    Container(ContainerRef),
+    /// Data that does not match the field's data type, kept as stored.
+    Unknown(Vec<u8>),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Empty => Ok(()),
+            Value::Text(s) => write!(f, "{}", s),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Date(d) => write!(f, "{}", d),
+            Value::Time(t) => write!(f, "{}", t),
+            Value::Timestamp(d, t) => write!(f, "{} {}", d, t),
+            Value::Container(c) => write!(f, "{}", c.reference),
+            Value::Unknown(bytes) => write!(f, "<{} bytes>", bytes.len()),
+        }
+    }
+}
+
+/// One record of a table.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Record {
+    pub id: usize,
+    /// Values by field id, one per repetition. Fields without any stored value are left out.
+    pub values: BTreeMap<u16, Vec<Value>>,
+}
+
+impl Record {
+    /// The value of a field's repetition, starting at 1.
+    pub fn value(&self, field: u16, repetition: usize) -> Option<&Value> {
+        self.values.get(&field)?.get(repetition.checked_sub(1)?)
+    }
+}
+
+/* Days counted from 0001-01-01 as day 1, converted through days since 1970-01-01. */
+fn date_from_days(days: i64) -> Date {
+    let z = days - 1 - 719_162 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = (yoe + era * 400 + (month <= 2) as i64) as i32;
+    Date { year, month, day }
+}
+
+fn time_from_seconds(seconds: u64) -> Time {
+    Time {
+        hours: (seconds / 3600) as u32,
+        minutes: (seconds / 60 % 60) as u8,
+        seconds: (seconds % 60) as u8,
+    }
+}
+
+/* Text, numbers and container references are stored as XOR encoded text. Dates are a
+ * 4 byte day number, times 4 byte seconds since midnight and timestamps 8 byte seconds,
+ * all big endian and counted from 0001-01-01. */
+pub fn decode_value(data_type: DataType, data: &[u8]) -> Value {
+    if data.is_empty() {
+        return Value::Empty;
+    }
+    match data_type {
+        DataType::Text => Value::Text(fm_string_decrypt(data)),
+        DataType::Number => {
+            let text = fm_string_decrypt(data);
+            match text.parse::<Decimal>() {
+                Ok(n) => Value::Number(n),
+                /* Number fields accept any text, only numeric entries become numbers. */
+                Err(_) => Value::Text(text),
+            }
+        },
+        DataType::Date if data.len() == 4 => Value::Date(date_from_days(get_int(data) as i64)),
+        DataType::Time if data.len() == 4 => Value::Time(time_from_seconds(get_int(data) as u64)),
+        DataType::Timestamp if data.len() == 8 => {
+            let seconds = data.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+            Value::Timestamp(date_from_days((seconds / 86_400) as i64 + 1), time_from_seconds(seconds % 86_400))
+        },
+        DataType::Container => Value::Container(ContainerRef { reference: fm_string_decrypt(data) }),
+        _ => Value::Unknown(data.to_vec()),
+    }
+}
+
+fn decode_record(id: usize, raw: &RawRecord, table: Option<&FMComponentTable>) -> Record {
+    let values = raw.iter()
+        .map(|(field, repetitions)| {
+            let data_type = table.and_then(|t| t.fields.get(field)).map(|f| f.data_type);
+            let count = repetitions.keys().max().copied().unwrap_or(0);
+            let mut values = vec![Value::Empty; count];
+            for (repetition, data) in repetitions {
+                if let Some(value) = repetition.checked_sub(1).and_then(|i| values.get_mut(i)) {
+                    *value = match data_type {
+                        Some(data_type) => decode_value(data_type, data),
+                        None => Value::Unknown(data.clone()),
+                    };
+                }
+            }
+            (*field, values)
+        })
+        .collect();
+    Record { id, values }
+}
+
+/// Where a file was decompiled from, reopened to read its records when they are asked for.
+#[derive(Debug, Clone, Default)]
+pub(crate) enum RecordSource {
+    /// Decompiled from a reader that cannot be reopened.
+    #[default]
+    None,
+    /// The file's size and modification time when it was decompiled, to notice it was replaced.
+    Path { path: PathBuf, len: u64, modified: Option<SystemTime> },
+    Bytes(Arc<[u8]>),
+}
+
+impl RecordSource {
+    pub(crate) fn path(path: PathBuf) -> Result<Self, FmpError> {
+        let metadata = std::fs::metadata(&path)?;
+        Ok(RecordSource::Path { path, len: metadata.len(), modified: metadata.modified().ok() })
+    }
+
+    /* A reopened file must still be the one that was decompiled, down to its header. */
+    fn open(&self, header: &FileHeader) -> Result<SourceReader, FmpError> {
+        let mut reader = match self {
+            RecordSource::None => return Err(FmpError::SourceUnavailable("file was decompiled from a reader")),
+            RecordSource::Path { path, len, modified } => {
+                let file = File::open(path)?;
+                let metadata = file.metadata()?;
+                if metadata.len() != *len || metadata.modified().ok() != *modified {
+                    return Err(FmpError::SourceUnavailable("file changed since it was decompiled"));
+                }
+                SourceReader::File(BufReader::new(file))
+            },
+            RecordSource::Bytes(bytes) => SourceReader::Bytes(Cursor::new(bytes.clone())),
+        };
+        if read_fmp12_header(&mut reader)? != *header {
+            return Err(FmpError::SourceUnavailable("file changed since it was decompiled"));
+        }
+        Ok(reader)
+    }
+}
+
+enum SourceReader {
+    File(BufReader<File>),
+    Bytes(Cursor<Arc<[u8]>>),
+}
+
+impl Read for SourceReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            SourceReader::File(reader) => reader.read(buf),
+            SourceReader::Bytes(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl Seek for SourceReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            SourceReader::File(reader) => reader.seek(pos),
+            SourceReader::Bytes(reader) => reader.seek(pos),
+        }
+    }
+}
+
+/// Iterator over the records of a table, in order of their ids. Sectors are read one at a
+/// time as the iterator advances, only the record being assembled is held in memory.
+pub struct Records<'a> {
+    reader: SourceReader,
+    /* The table's directory, the table id plus 128. */
+    directory: usize,
+    table: Option<&'a FMComponentTable>,
+    buffer: [u8; SECTOR_SIZE],
+    /* 0 once the end of the sector chain was reached. */
+    next_sector: usize,
+    visited: HashSet<usize>,
+    current: Option<(usize, RawRecord)>,
+    ready: VecDeque<Record>,
+}
+
+impl<'a> Records<'a> {
+    pub(crate) fn new(source: &RecordSource, header: &FileHeader, table: usize, definition: Option<&'a FMComponentTable>)
+        -> Result<Self, FmpError> {
+        Ok(Self {
+            reader: source.open(header)?,
+            directory: table + 128,
+            table: definition,
+            buffer: [0; SECTOR_SIZE],
+            next_sector: 2,
+            visited: HashSet::new(),
+            current: None,
+            ready: VecDeque::new(),
+        })
+    }
+
+    /* Records are stored in order, one is complete once data for the next one appears. */
+    fn read_next_sector(&mut self) -> Result<(), FmpError> {
+        let idx = self.next_sector;
+        if !self.visited.insert(idx) {
+            return Err(FmpError::InvalidSectorChain { sector: idx });
+        }
+        read_sector(&mut self.reader, idx, &mut self.buffer)?;
+        self.next_sector = sector::get_sector(&self.buffer, idx)?.next;
+
+        let mut path = Vec::<usize>::new();
+        let mut offset = 20;
+        while offset < SECTOR_SIZE {
+            let chunk = get_chunk_from_code(&self.buffer, &mut offset, &mut path, idx)?;
+            /* The first repetition is keyed by field, further ones sit in a directory per field */
+            let (record, field, repetition, data) = match (path.as_slice(), chunk.ref_simple, chunk.data) {
+                ([x, 5, record], Some(field), Some(data)) if *x == self.directory => (*record, field, 1, data),
+                ([x, 5, record, field], Some(repetition), Some(data)) if *x == self.directory => {
+                    (*record, *field as u16, repetition as usize, data)
+                },
+                _ => continue,
+            };
+            if self.current.as_ref().is_none_or(|(id, _)| *id != record) {
+                if let Some((id, raw)) = self.current.replace((record, RawRecord::new())) {
+                    self.ready.push_back(decode_record(id, &raw, self.table));
+                }
+            }
+            if let Some((_, raw)) = self.current.as_mut() {
+                raw.entry(field).or_default().insert(repetition, data.to_vec());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for Records<'_> {
+    type Item = Result<Record, FmpError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.ready.pop_front() {
+                return Some(Ok(record));
+            }
+            if self.next_sector == 0 {
+                let (id, raw) = self.current.take()?;
+                return Some(Ok(decode_record(id, &raw, self.table)));
+            }
+            if let Err(e) = self.read_next_sector() {
+                self.next_sector = 0;
+                self.current = None;
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decompile::decompiler::{decompile_fmp12_bytes, decompile_fmp12_file};
+    use crate::repr::component::{FMComponentField, FMComponentTable};
+    use crate::repr::file::FmpFile;
+    use crate::repr::record::*;
+    use crate::util::test_util::{encrypt, sector_chain_file, single_sector_file};
+
+    #[test]
+    fn value_decoding() {
+        assert_eq!(decode_value(DataType::Text, &encrypt("Acme")), Value::Text("Acme".to_string()));
+        assert_eq!(decode_value(DataType::Number, &encrypt("12.50")).to_string(), "12.50");
+        assert_eq!(decode_value(DataType::Number, &encrypt("n/a")), Value::Text("n/a".to_string()));
+        assert_eq!(decode_value(DataType::Date, &1u32.to_be_bytes()).to_string(), "0001-01-01");
+        assert_eq!(decode_value(DataType::Date, &738_886u32.to_be_bytes()).to_string(), "2024-01-01");
+        assert_eq!(decode_value(DataType::Time, &45_296u32.to_be_bytes()).to_string(), "12:34:56");
+        let seconds = 738_885u64 * 86_400 + 3_600;
+        assert_eq!(decode_value(DataType::Timestamp, &seconds.to_be_bytes()).to_string(), "2024-01-01 01:00:00");
+        let Value::Container(container) = decode_value(DataType::Container, &encrypt("image:/Photos/logo.png")) else { panic!() };
+        assert_eq!(container.file_name(), "logo.png");
+        assert_eq!(decode_value(DataType::Date, &[1, 2]), Value::Unknown(vec![1, 2]));
+        assert_eq!(decode_value(DataType::Text, &[]), Value::Empty);
+    }
+
+    /* A simple data chunk holding text */
+    fn text(key: u8, s: &str) -> Vec<u8> {
+        [vec![0x06, key, s.len() as u8], encrypt(s)].concat()
+    }
+
+    #[test]
+    fn table_records() {
+        let mut file = FmpFile::new();
+        let mut table = FMComponentTable::new();
+        let mut name = FMComponentField::new();
+        name.data_type = DataType::Text;
+        table.fields.insert(1, name);
+        let mut phone = FMComponentField::new();
+        phone.data_type = DataType::Text;
+        phone.repetitions = 3;
+        table.fields.insert(2, phone);
+        file.tables.insert(1, table);
+
+        /* Record 7, then record 8 of table 1 continuing into the next sector, then a record of table 2 */
+        let first = [
+            vec![0x20, 0x81, 0x20, 0x05, 0x20, 0x07], text(1, "Acme"),
+            vec![0x20, 0x02], text(3, "555-0100"), vec![0x40, 0x40],
+            vec![0x20, 0x08], text(1, "Beta"), vec![0x40, 0x40, 0x40],
+        ].concat();
+        let second = [
+            vec![0x20, 0x81, 0x20, 0x05, 0x20, 0x08, 0x20, 0x02], text(1, "555"), vec![0x40, 0x40, 0x40, 0x40],
+            vec![0x20, 0x82, 0x20, 0x05, 0x20, 0x01], text(1, "Hi"), vec![0x40, 0x40, 0x40],
+        ].concat();
+        let bytes = sector_chain_file(&[&first, &second]);
+        file.header = read_fmp12_header(Cursor::new(&bytes)).unwrap();
+        file.source = RecordSource::Bytes(bytes.as_slice().into());
+
+        let records = file.records(1).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].id, 7);
+        assert_eq!(records[0].value(1, 1), Some(&Value::Text("Acme".to_string())));
+        assert_eq!(records[0].values[&2], [Value::Empty, Value::Empty, Value::Text("555-0100".to_string())]);
+        assert_eq!(records[1].id, 8);
+        assert_eq!(records[1].value(1, 1), Some(&Value::Text("Beta".to_string())));
+        assert_eq!(records[1].value(2, 1), Some(&Value::Text("555".to_string())));
+
+        /* Without a definition for the table, values are kept as stored */
+        let other = file.records(2).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(other[0].value(1, 1), Some(&Value::Unknown(encrypt("Hi"))));
+        assert_eq!(file.records(3).unwrap().count(), 0);
+
+        /* Records completed before a read fails are still returned */
+        file.source = RecordSource::Bytes(bytes[..SECTOR_SIZE * 3].into());
+        let mut truncated = file.records(1).unwrap();
+        assert_eq!(truncated.next().unwrap().unwrap().id, 7);
+        assert!(matches!(truncated.next(), Some(Err(FmpError::TruncatedSector { sector: 3, .. }))));
+        assert!(truncated.next().is_none());
+
+        file.source = RecordSource::None;
+        assert!(matches!(file.records(1), Err(FmpError::SourceUnavailable(_))));
+    }
+
+    #[test]
+    fn decompiled_records() {
+        /* Table 1 "Contacts" with Text field 1 "Name" and Number field 2 "Score" of 3 repetitions,
+         * then record 7 with "Acme" and 42 in the second repetition of Score */
+        let switches = |data_type: u8, repetitions: u8| {
+            let mut switches = vec![0x06, 0x02, 26, 0, data_type];
+            switches.extend([0; 24]);
+            switches[3 + 25] = repetitions;
+            switches
+        };
+        let payload = [
+            vec![0x20, 0x03, 0x20, 0x10, 0x20, 0x05, 0x20, 0x81, 0x06, 0x10, 0x08], encrypt("Contacts"),
+            vec![0x40, 0x40, 0x40, 0x40],
+            vec![0x20, 0x81, 0x20, 0x03, 0x20, 0x05],
+            vec![0x20, 0x01, 0x06, 0x10, 0x04], encrypt("Name"), switches(1, 1), vec![0x40],
+            vec![0x20, 0x02, 0x06, 0x10, 0x05], encrypt("Score"), switches(2, 3), vec![0x40],
+            vec![0x40, 0x40],
+            vec![0x20, 0x05, 0x20, 0x07, 0x06, 0x01, 0x04], encrypt("Acme"),
+            vec![0x20, 0x02, 0x06, 0x02, 0x02], encrypt("42"), vec![0x40, 0x40, 0x40, 0x40],
+        ].concat();
+        let bytes = single_sector_file(&payload);
+        let file = decompile_fmp12_bytes(&bytes).unwrap();
+        assert_eq!(file.tables[&1].table_name, "Contacts");
+        let records = file.records(1).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].id, 7);
+        assert_eq!(records[0].value(1, 1), Some(&Value::Text("Acme".to_string())));
+        assert_eq!(records[0].values[&2], [Value::Empty, Value::Number("42".parse().unwrap())]);
+
+        /* Files are reopened for their records, and refused once they changed */
+        let path = std::env::temp_dir().join(format!("fmplib_records_{}.fmp12", std::process::id()));
+        std::fs::write(&path, &bytes).unwrap();
+        let file = decompile_fmp12_file(&path).unwrap();
+        assert_eq!(file.records(1).unwrap().count(), 1);
+        std::fs::write(&path, sector_chain_file(&[&payload, &[]])).unwrap();
+        assert!(matches!(file.records(1), Err(FmpError::SourceUnavailable(_))));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,134 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::util::format_decode::get_int;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Comparison {
+    #[default]
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    /// Cartesian product, every record matches.
+    All,
+    Unknown(u8),
+}
+
+impl From<u8> for Comparison {
+    fn from(byte: u8) -> Self {
+        match byte {
+            0 => Comparison::Equal,
+            1 => Comparison::NotEqual,
+            2 => Comparison::Less,
+            3 => Comparison::LessEqual,
+            4 => Comparison::Greater,
+            5 => Comparison::GreaterEqual,
+            6 => Comparison::All,
+            n => Comparison::Unknown(n),
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Comparison::Equal => write!(f, "="),
+            Comparison::NotEqual => write!(f, "≠"),
+            Comparison::Less => write!(f, "<"),
+            Comparison::LessEqual => write!(f, "≤"),
+            Comparison::Greater => write!(f, ">"),
+            Comparison::GreaterEqual => write!(f, "≥"),
+            Comparison::All => write!(f, "×"),
+            Comparison::Unknown(n) => write!(f, "<comparison {}>", n),
+        }
+    }
+}
+
+/// One `left operator right` line of a relationship.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Predicate {
+    /// Field of the relationship's first table occurrence.
+    pub field1: u16,
+    pub field1_name: String,
+    pub comparison: Comparison,
+    /// Field of the relationship's second table occurrence.
+    pub field2: u16,
+    pub field2_name: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SortField {
+    pub field: u16,
+    pub field_name: String,
+    pub descending: bool,
+}
+
+/// Options set on one side of a relationship.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JoinOptions {
+    pub allow_creation: bool,
+    pub delete_related: bool,
+    /// Empty when the related records are not sorted.
+    pub sort: Vec<SortField>,
+}
+
+/* Predicates are stored back to back, 5 bytes each: comparison, field1, field2. */
+pub fn decode_predicates(data: &[u8]) -> Vec<Predicate> {
+    data.chunks_exact(5)
+        .map(|p| Predicate {
+            field1: get_int(&p[1..3]) as u16,
+            comparison: Comparison::from(p[0]),
+            field2: get_int(&p[3..5]) as u16,
+            ..Predicate::default()
+        })
+        .collect()
+}
+
+/* Options for the first side, then the second: a flags byte, a sort field count,
+ * then 3 bytes per sort field for the field id and direction. */
+pub fn decode_join_options(data: &[u8]) -> (JoinOptions, JoinOptions) {
+    let mut offset = 0;
+    let mut side = || {
+        let flags = data.get(offset).copied().unwrap_or(0);
+        let count = data.get(offset + 1).copied().unwrap_or(0) as usize;
+        let sort = data.get(offset + 2..)
+            .unwrap_or(&[])
+            .chunks_exact(3)
+            .take(count)
+            .map(|s| SortField {
+                field: get_int(&s[0..2]) as u16,
+                field_name: String::new(),
+                descending: s[2] & 0x01 != 0,
+            })
+            .collect::<Vec<_>>();
+        offset += 2 + sort.len() * 3;
+        JoinOptions {
+            allow_creation: flags & 0x01 != 0,
+            delete_related: flags & 0x02 != 0,
+            sort,
+        }
+    };
+    let first = side();
+    (first, side())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::repr::relationship::*;
+
+    #[test]
+    fn relationship_data() {
+        let predicates = decode_predicates(&[0, 0, 1, 0, 4, 3, 0, 2, 0, 7, 9]);
+        assert_eq!(predicates.len(), 2);
+        assert_eq!((predicates[0].field1, predicates[0].comparison, predicates[0].field2), (1, Comparison::Equal, 4));
+        assert_eq!(predicates[1].comparison.to_string(), "≤");
+
+        let (first, second) = decode_join_options(&[0x01, 1, 0, 5, 1, 0x03, 0]);
+        assert!(first.allow_creation && !first.delete_related);
+        assert_eq!(first.sort, vec![SortField { field: 5, field_name: String::new(), descending: true }]);
+        assert!(second.allow_creation && second.delete_related);
+        assert!(second.sort.is_empty());
+    }
+}
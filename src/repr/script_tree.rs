@@ -0,0 +1,138 @@
+use std::collections::{BTreeMap, HashSet};
+use serde::{Deserialize, Serialize};
+use crate::util::format_decode::get_int;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CatalogKind {
+    #[default]
+    Script,
+    Folder,
+    Separator,
+}
+
+/// An entry of the script catalog at [17].[1], before it is split into scripts and folders.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CatalogEntry {
+    pub kind: CatalogKind,
+    pub name: String,
+    /// Id of the containing folder, 0 at the top level.
+    pub parent: usize,
+    /// Place among the entries of the same folder.
+    pub position: usize,
+    pub include_in_menu: bool,
+    pub full_access: bool,
+}
+
+/// A script, folder or separator in Script Workspace order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScriptTreeItem {
+    Script { id: usize, name: String },
+    Folder { id: usize, name: String, children: Vec<ScriptTreeItem> },
+    Separator { id: usize },
+}
+
+/* Catalog switches at key 2: the kind, flags, parent folder and position. */
+pub fn decode_catalog_switches(entry: &mut CatalogEntry, switches: &[u8]) {
+    let byte = |i: usize| switches.get(i).copied().unwrap_or(0);
+    entry.kind = match byte(0) {
+        1 => CatalogKind::Folder,
+        2 => CatalogKind::Separator,
+        _ => CatalogKind::Script,
+    };
+    entry.include_in_menu = byte(1) & 0x01 != 0;
+    entry.full_access = byte(1) & 0x02 != 0;
+    entry.parent = switches.get(2..4).map_or(0, get_int);
+    entry.position = switches.get(4..6).map_or(0, get_int);
+}
+
+/// Names of the folders containing `id`, outermost first.
+pub fn folder_path(entries: &BTreeMap<usize, CatalogEntry>, id: usize) -> Vec<String> {
+    let mut path = Vec::new();
+    let mut seen = HashSet::from([id]);
+    let mut parent = entries.get(&id).map_or(0, |e| e.parent);
+    while let Some(folder) = entries.get(&parent) {
+        if !seen.insert(parent) {
+            break;
+        }
+        path.push(folder.name.clone());
+        parent = folder.parent;
+    }
+    path.reverse();
+    path
+}
+
+/// Nest the catalog under its folders. Entries whose folder is missing are kept at the top level.
+pub fn build_script_tree(entries: &BTreeMap<usize, CatalogEntry>) -> Vec<ScriptTreeItem> {
+    let mut children = BTreeMap::<usize, Vec<(usize, usize)>>::new();
+    for (id, entry) in entries {
+        let parent = match entries.get(&entry.parent) {
+            Some(folder) if folder.kind == CatalogKind::Folder && entry.parent != *id => entry.parent,
+            _ => 0,
+        };
+        children.entry(parent).or_default().push((entry.position, *id));
+    }
+    for items in children.values_mut() {
+        items.sort();
+    }
+
+    fn build(parent: usize, entries: &BTreeMap<usize, CatalogEntry>, children: &BTreeMap<usize, Vec<(usize, usize)>>,
+             seen: &mut HashSet<usize>) -> Vec<ScriptTreeItem> {
+        let mut items = Vec::new();
+        for (_, id) in children.get(&parent).into_iter().flatten() {
+            if !seen.insert(*id) {
+                continue;
+            }
+            let entry = &entries[id];
+            items.push(match entry.kind {
+                CatalogKind::Script => ScriptTreeItem::Script { id: *id, name: entry.name.clone() },
+                CatalogKind::Folder => ScriptTreeItem::Folder {
+                    id: *id,
+                    name: entry.name.clone(),
+                    children: build(*id, entries, children, seen),
+                },
+                CatalogKind::Separator => ScriptTreeItem::Separator { id: *id },
+            });
+        }
+        items
+    }
+    build(0, entries, &children, &mut HashSet::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use crate::repr::script_tree::*;
+
+    fn entry(kind: CatalogKind, name: &str, parent: usize, position: usize) -> CatalogEntry {
+        CatalogEntry { kind, name: name.to_string(), parent, position, ..CatalogEntry::default() }
+    }
+
+    #[test]
+    fn script_tree() {
+        let mut flags = CatalogEntry::default();
+        decode_catalog_switches(&mut flags, &[1, 3, 0, 4, 0, 2]);
+        assert_eq!((flags.kind, flags.include_in_menu, flags.full_access), (CatalogKind::Folder, true, true));
+        assert_eq!((flags.parent, flags.position), (4, 2));
+
+        let entries = BTreeMap::from([
+            (1, entry(CatalogKind::Script, "Startup", 0, 0)),
+            (2, entry(CatalogKind::Folder, "Invoices", 0, 2)),
+            (3, entry(CatalogKind::Script, "New Invoice", 2, 1)),
+            (4, entry(CatalogKind::Separator, "", 0, 1)),
+            (5, entry(CatalogKind::Script, "Print Invoice", 6, 0)),
+            (6, entry(CatalogKind::Folder, "Print", 2, 0)),
+        ]);
+        assert_eq!(build_script_tree(&entries), vec![
+            ScriptTreeItem::Script { id: 1, name: "Startup".to_string() },
+            ScriptTreeItem::Separator { id: 4 },
+            ScriptTreeItem::Folder { id: 2, name: "Invoices".to_string(), children: vec![
+                ScriptTreeItem::Folder { id: 6, name: "Print".to_string(), children: vec![
+                    ScriptTreeItem::Script { id: 5, name: "Print Invoice".to_string() },
+                ] },
+                ScriptTreeItem::Script { id: 3, name: "New Invoice".to_string() },
+            ] },
+        ]);
+        assert_eq!(folder_path(&entries, 5), vec!["Invoices", "Print"]);
+        assert!(folder_path(&entries, 1).is_empty());
+    }
+}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use crate::repr::component::{FMComponentAccount, FMComponentPrivilegeSet};
+use crate::util::format_decode::get_int;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AccountType {
+    #[default]
+    FileMaker,
+    /// Authenticated by an external server or identity provider.
+    External,
+    Unknown(u8),
+}
+
+impl From<u8> for AccountType {
+    fn from(byte: u8) -> Self {
+        match byte {
+            0 => AccountType::FileMaker,
+            1 => AccountType::External,
+            n => AccountType::Unknown(n),
+        }
+    }
+}
+
+/// Access a privilege set grants to a layout, value list or script.
+/// For scripts `ViewOnly` means the script can be run but not edited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AccessLevel {
+    #[default]
+    NoAccess,
+    ViewOnly,
+    Modifiable,
+    Unknown(u8),
+}
+
+impl From<u8> for AccessLevel {
+    fn from(byte: u8) -> Self {
+        match byte {
+            0 => AccessLevel::NoAccess,
+            1 => AccessLevel::ViewOnly,
+            2 => AccessLevel::Modifiable,
+            n => AccessLevel::Unknown(n),
+        }
+    }
+}
+
+/// Record privileges a privilege set grants on one table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct RecordAccess {
+    pub view: bool,
+    pub edit: bool,
+    pub create: bool,
+    pub delete: bool,
+}
+
+/* Byte 0 of the table's switches: 1 view, 2 edit, 4 create, 8 delete. */
+pub fn decode_record_access(data: &[u8]) -> RecordAccess {
+    let flags = data.first().copied().unwrap_or(0);
+    RecordAccess {
+        view: flags & 0x01 != 0,
+        edit: flags & 0x02 != 0,
+        create: flags & 0x04 != 0,
+        delete: flags & 0x08 != 0,
+    }
+}
+
+/* Switches at key 2: byte 0 type, byte 1 flags, bytes 2-3 privilege set.
+ * Password material lives under other keys and is never read. */
+pub fn decode_account_switches(account: &mut FMComponentAccount, switches: &[u8]) {
+    account.account_type = AccountType::from(switches.first().copied().unwrap_or(0));
+    account.active = switches.get(1).is_some_and(|flags| flags & 0x01 != 0);
+    account.privilege_set = switches.get(2..4).map_or(0, get_int);
+}
+
+/* Privilege sets holding an extended privilege, 2 bytes each. */
+pub fn decode_privilege_set_ids(data: &[u8]) -> Vec<usize> {
+    data.chunks_exact(2).map(get_int).collect()
+}
+
+/* Switches at key 2 of a privilege set: byte 0 flags, 1 = full access. */
+pub fn decode_privilege_set_switches(set: &mut FMComponentPrivilegeSet, switches: &[u8]) {
+    set.full_access = switches.first().is_some_and(|flags| flags & 0x01 != 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::repr::component::FMComponentAccount;
+    use crate::repr::security::*;
+
+    #[test]
+    fn security_decoding() {
+        let mut account = FMComponentAccount::new();
+        decode_account_switches(&mut account, &[1, 1, 0, 3]);
+        assert_eq!(account.account_type, AccountType::External);
+        assert!(account.active);
+        assert_eq!(account.privilege_set, 3);
+
+        let access = decode_record_access(&[0x05]);
+        assert!(access.view && !access.edit && access.create && !access.delete);
+        assert_eq!(AccessLevel::from(1), AccessLevel::ViewOnly);
+        assert_eq!(decode_privilege_set_ids(&[0, 1, 0, 4, 9]), [1, 4]);
+    }
+}
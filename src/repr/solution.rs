@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+use crate::decompile::decompiler::decompile_fmp12_file;
+use crate::error::FmpError;
+use crate::repr::data_source::{BaseTable, DataSourceType, PathPrefix};
+use crate::repr::file::FmpFile;
+use crate::repr::value_list::ValueListSource;
+use crate::script_engine::step_params::StepParams;
+
+/// The component of a file that refers into another file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReferenceSource {
+    /// A table occurrence whose base table is defined in the other file.
+    TableOccurrence { table_occurrence: usize },
+    /// A `Perform Script` step, by script id and step position.
+    PerformScript { script: usize, step: usize },
+    /// A value list that uses a value list of the other file.
+    ValueList { value_list: usize },
+}
+
+/// A reference from one file of a solution to a table, script or value list of another.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrossFileReference {
+    /// Index of the referring file in `Solution::files`.
+    pub file: usize,
+    pub source: ReferenceSource,
+    pub data_source: u16,
+    pub data_source_name: String,
+    /// Index of the file the data source points at, `None` if it was not supplied.
+    pub target_file: Option<usize>,
+    /// Id of the table, script or value list in the target file.
+    pub target: usize,
+    /// Empty when the target file or the component in it is missing.
+    pub target_name: String,
+}
+
+/// A set of files opened together, with the references between them resolved.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Solution {
+    pub files: Vec<FmpFile>,
+    /// The name data sources find each file by, the stem of the path it was opened from.
+    pub file_names: Vec<String>,
+    pub references: Vec<CrossFileReference>,
+}
+
+fn file_stem(name: &str) -> String {
+    name.strip_suffix(".fmp12").unwrap_or(name).to_lowercase()
+}
+
+/* The first path of a FileMaker data source naming one of the supplied files wins,
+ * the same order FileMaker tries them in. */
+fn resolve_data_source(files: &[FmpFile], names: &[String], file: usize, data_source: u16) -> Option<usize> {
+    let source = files[file].data_sources.get(&(data_source as usize))?;
+    if source.source_type != DataSourceType::FileMaker {
+        return None;
+    }
+    source.paths.iter().find_map(|path| {
+        let name = file_stem(path.file_name());
+        names.iter().position(|n| file_stem(n) == name)
+    })
+}
+
+fn find_references(files: &[FmpFile], names: &[String]) -> Vec<CrossFileReference> {
+    let mut references = Vec::new();
+    for (index, file) in files.iter().enumerate() {
+        let mut found = Vec::new();
+
+        let mut occurrences = file.table_occurrences.iter().collect::<Vec<_>>();
+        occurrences.sort_by_key(|(id, _)| **id);
+        for (id, occurrence) in occurrences {
+            if let BaseTable::External { data_source, table } = occurrence.base_table() {
+                found.push((ReferenceSource::TableOccurrence { table_occurrence: *id }, data_source, table as usize));
+            }
+        }
+
+        let mut scripts = file.scripts.iter().collect::<Vec<_>>();
+        scripts.sort_by_key(|(id, _)| **id);
+        for (id, script) in scripts {
+            let mut steps = script.instructions.iter().collect::<Vec<_>>();
+            steps.sort_by_key(|(position, _)| **position);
+            for (position, step) in steps {
+                if let StepParams::PerformScript { script: target, from_file: Some(data_source), .. } = step.params {
+                    found.push((ReferenceSource::PerformScript { script: *id, step: *position }, data_source as u16, target));
+                }
+            }
+        }
+
+        let mut value_lists = file.value_lists.iter().collect::<Vec<_>>();
+        value_lists.sort_by_key(|(id, _)| **id);
+        for (id, list) in value_lists {
+            if let ValueListSource::ExternalFile { data_source, value_list } = list.source {
+                found.push((ReferenceSource::ValueList { value_list: *id }, data_source, value_list as usize));
+            }
+        }
+
+        for (source, data_source, target) in found {
+            let target_file = resolve_data_source(files, names, index, data_source);
+            let target_name = target_file.and_then(|t| match source {
+                ReferenceSource::TableOccurrence { .. } => files[t].tables.get(&target).map(|t| t.table_name.clone()),
+                ReferenceSource::PerformScript { .. } => files[t].scripts.get(&target).map(|s| s.script_name.clone()),
+                ReferenceSource::ValueList { .. } => files[t].value_lists.get(&target).map(|l| l.list_name.clone()),
+            });
+            references.push(CrossFileReference {
+                file: index,
+                source,
+                data_source,
+                data_source_name: file.data_sources.get(&(data_source as usize))
+                    .map(|s| s.source_name.clone())
+                    .unwrap_or_default(),
+                target_file,
+                target,
+                target_name: target_name.unwrap_or_default(),
+            });
+        }
+    }
+    references
+}
+
+impl Solution {
+    /// Decompile every file at `paths` and link them together, finding each by its path's file name.
+    pub fn open<P: AsRef<Path>>(paths: &[P]) -> Result<Solution, FmpError> {
+        let files = paths.iter()
+            .map(|path| decompile_fmp12_file(path.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let names = paths.iter()
+            .map(|path| path.as_ref().file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default())
+            .collect();
+        Ok(Solution::link(files, names))
+    }
+
+    /// Link already decompiled files, naming the base tables, fields, scripts and value lists
+    /// they use from each other. Files are found by the name stored in their header.
+    pub fn new(files: Vec<FmpFile>) -> Solution {
+        let names = files.iter().map(|f| f.name.clone()).collect();
+        Solution::link(files, names)
+    }
+
+    fn link(mut files: Vec<FmpFile>, names: Vec<String>) -> Solution {
+        let references = find_references(&files, &names);
+        for reference in references.iter().filter(|r| !r.target_name.is_empty()) {
+            let file = &mut files[reference.file];
+            match reference.source {
+                ReferenceSource::TableOccurrence { table_occurrence } => {
+                    if let Some(occurrence) = file.table_occurrences.get_mut(&table_occurrence) {
+                        occurrence.table_actual_name = reference.target_name.clone();
+                    }
+                },
+                ReferenceSource::PerformScript { script, step } => {
+                    let step = file.scripts.get_mut(&script).and_then(|s| s.instructions.get_mut(&step));
+                    if let Some(StepParams::PerformScript { script_name, .. }) = step.map(|s| &mut s.params) {
+                        *script_name = reference.target_name.clone();
+                    }
+                },
+                ReferenceSource::ValueList { value_list } => {
+                    if let Some(list) = file.value_lists.get_mut(&value_list) {
+                        list.external_list_name = reference.target_name.clone();
+                    }
+                },
+            }
+        }
+
+        /* Fields are named once every table occurrence knows the file its base table is in */
+        let external_fields = (0..files.len())
+            .map(|file| {
+                let mut names_by_ref = HashMap::new();
+                for (id, occurrence) in &files[file].table_occurrences {
+                    let BaseTable::External { data_source, table } = occurrence.base_table() else { continue };
+                    let Some(target) = resolve_data_source(&files, &names, file, data_source) else { continue };
+                    for (field, f) in files[target].tables.get(&(table as usize)).iter().flat_map(|t| &t.fields) {
+                        names_by_ref.insert((*id, *field as usize), format!("{}::{}", occurrence.table_occurence_name, f.field_name));
+                    }
+                }
+                names_by_ref
+            })
+            .collect::<Vec<_>>();
+        for (file, fields) in files.iter_mut().zip(external_fields) {
+            file.resolve_external_fields(&|occurrence, field| fields.get(&(occurrence, field)).cloned());
+        }
+        Solution { files, file_names: names, references }
+    }
+
+    /// The file named `name`, with or without its `.fmp12` extension.
+    pub fn file(&self, name: &str) -> Option<&FmpFile> {
+        let position = self.file_names.iter().position(|n| file_stem(n) == file_stem(name))?;
+        self.files.get(position)
+    }
+
+    /// References into files that were not supplied, or to components missing from them.
+    pub fn unresolved(&self) -> impl Iterator<Item = &CrossFileReference> {
+        self.references.iter().filter(|r| r.target_name.is_empty())
+    }
+
+    /// Names of the FileMaker data sources pointing at files that were not supplied, without duplicates.
+    /// ODBC sources are left out, they never point at a file.
+    pub fn missing_files(&self) -> Vec<String> {
+        let is_file = |r: &CrossFileReference| self.files[r.file].data_sources.get(&(r.data_source as usize))
+            .is_some_and(|s| s.source_type == DataSourceType::FileMaker && s.paths.iter().any(|p| p.prefix != PathPrefix::None));
+        let mut missing = Vec::<String>::new();
+        for reference in self.references.iter().filter(|r| r.target_file.is_none() && is_file(r)) {
+            if !missing.contains(&reference.data_source_name) {
+                missing.push(reference.data_source_name.clone());
+            }
+        }
+        missing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::calc_engine::calc::{BinaryOp, Calc, FieldRef};
+    use crate::repr::component::*;
+    use crate::repr::data_source::decode_paths;
+    use crate::repr::file::FmpFile;
+    use crate::repr::solution::*;
+    use crate::script_engine::instructions::{Instruction, ScriptStep};
+
+    fn data_source(name: &str, paths: &str) -> FMComponentDataSource {
+        let mut source = FMComponentDataSource::new();
+        source.source_name = name.to_string();
+        source.paths = decode_paths(paths);
+        source
+    }
+
+    fn field(table_occurrence: usize, field: usize) -> FieldRef {
+        FieldRef { table_occurrence, field, name: None }
+    }
+
+    #[test]
+    fn solution_linking() {
+        let mut invoices = FmpFile::new();
+        invoices.name = "Invoices.fmp12".to_string();
+        invoices.data_sources.insert(1, data_source("CRM", "file:Missing\rfile:crm.fmp12"));
+        invoices.data_sources.insert(2, data_source("Archive", "file:Archive"));
+        let mut odbc = data_source("Warehouse", "warehouse_dsn");
+        odbc.source_type = DataSourceType::Odbc;
+        invoices.data_sources.insert(3, odbc);
+        let mut occurrence = FMComponentTableOccurence::new();
+        occurrence.table_occurence_name = "Customers".to_string();
+        occurrence.table_actual = 2;
+        occurrence.data_source = Some(1);
+        invoices.table_occurrences.insert(1, occurrence);
+        let mut stock = FMComponentTableOccurence::new();
+        stock.table_actual = 1;
+        stock.data_source = Some(3);
+        invoices.table_occurrences.insert(2, stock);
+        let mut script = FMComponentScript::new();
+        script.instructions.insert(0, ScriptStep {
+            opcode: Instruction::PerformScript,
+            index: 1,
+            params: StepParams::PerformScript { script: 5, script_name: String::new(), from_file: Some(1), parameter: None },
+            disabled: false,
+        });
+        /* Set Field [ CRM::Name ; Upper ( CRM::Name ) & CRM::Missing ] */
+        let calc = Calc::Binary {
+            op: BinaryOp::Concatenate,
+            left: Box::new(Calc::Call { function: 107, args: vec![Calc::Field(field(1, 3))] }),
+            right: Box::new(Calc::Field(field(1, 9))),
+        };
+        script.instructions.insert(1, ScriptStep {
+            opcode: Instruction::SetField,
+            index: 2,
+            params: StepParams::SetField { target: Some(field(1, 3)), calc: Some(Ok(calc)), repetition: None },
+            disabled: false,
+        });
+        invoices.scripts.insert(1, script);
+        let mut list = FMComponentValueList::new();
+        list.source = ValueListSource::ExternalFile { data_source: 2, value_list: 1 };
+        invoices.value_lists.insert(1, list);
+        let mut list = FMComponentValueList::new();
+        list.source = ValueListSource::ExternalFile { data_source: 1, value_list: 4 };
+        invoices.value_lists.insert(2, list);
+
+        /* Renamed on disk after it was created, data sources find it by the name it is opened as */
+        let mut crm = FmpFile::new();
+        crm.name = "Contacts.fmp12".to_string();
+        let mut table = FMComponentTable::new();
+        table.table_name = "Customers".to_string();
+        let mut field = FMComponentField::new();
+        field.field_name = "Name".to_string();
+        table.fields.insert(3, field);
+        crm.tables.insert(2, table);
+        let mut script = FMComponentScript::new();
+        script.script_name = "Sync".to_string();
+        crm.scripts.insert(5, script);
+        let mut list = FMComponentValueList::new();
+        list.list_name = "Regions".to_string();
+        crm.value_lists.insert(4, list);
+
+        let solution = Solution::link(vec![invoices, crm], vec!["Invoices".to_string(), "CRM".to_string()]);
+        assert_eq!(solution.references.len(), 5);
+        assert!(solution.file("contacts").is_none());
+        let invoices = solution.file("invoices.fmp12").unwrap();
+        assert_eq!(invoices.table_occurrences[&1].table_actual_name, "Customers");
+        assert_eq!(invoices.scripts[&1].instructions[&0].switches(), ["\"Sync\""]);
+        assert_eq!(invoices.scripts[&1].instructions[&1].switches(), ["Customers::Name", "Upper ( Customers::Name ) & <unresolved 1:9>"]);
+        assert_eq!(invoices.value_lists[&2].external_list_name, "Regions");
+        assert_eq!(solution.file("crm").unwrap().scripts[&5].script_name, "Sync");
+
+        let unresolved = solution.unresolved().map(|r| &r.source).collect::<Vec<_>>();
+        assert_eq!(unresolved, [&ReferenceSource::TableOccurrence { table_occurrence: 2 }, &ReferenceSource::ValueList { value_list: 1 }]);
+        assert_eq!(solution.missing_files(), ["Archive"]);
+    }
+}
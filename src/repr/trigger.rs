@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+use crate::calc_engine::calc::Calc;
+use crate::calc_engine::decode::CalcError;
+use crate::util::format_decode::get_int;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TriggerEvent {
+    #[default]
+    OnObjectEnter,
+    OnObjectKeystroke,
+    OnObjectModify,
+    OnObjectValidate,
+    OnObjectSave,
+    OnObjectExit,
+    OnPanelSwitch,
+    OnPopoverOpen,
+    OnPopoverClose,
+    OnRecordLoad,
+    OnRecordCommit,
+    OnRecordRevert,
+    OnLayoutKeystroke,
+    OnLayoutEnter,
+    OnLayoutExit,
+    OnLayoutSizeChange,
+    OnModeEnter,
+    OnModeExit,
+    OnViewChange,
+    OnGestureTap,
+    OnFirstWindowOpen,
+    OnLastWindowClose,
+    OnWindowOpen,
+    OnWindowClose,
+    OnFileAVPlayerChange,
+    OnObjectAVPlayerChange,
+    OnWindowTransaction,
+    OnExternalCommandReceived,
+    /// Not an event, the script a button or button bar segment performs when clicked.
+    ButtonAction,
+    Unknown(u8),
+}
+
+impl From<u8> for TriggerEvent {
+    fn from(byte: u8) -> Self {
+        match byte {
+            0 => TriggerEvent::OnObjectEnter,
+            1 => TriggerEvent::OnObjectKeystroke,
+            2 => TriggerEvent::OnObjectModify,
+            3 => TriggerEvent::OnObjectValidate,
+            4 => TriggerEvent::OnObjectSave,
+            5 => TriggerEvent::OnObjectExit,
+            6 => TriggerEvent::OnPanelSwitch,
+            7 => TriggerEvent::OnPopoverOpen,
+            8 => TriggerEvent::OnPopoverClose,
+            9 => TriggerEvent::OnRecordLoad,
+            10 => TriggerEvent::OnRecordCommit,
+            11 => TriggerEvent::OnRecordRevert,
+            12 => TriggerEvent::OnLayoutKeystroke,
+            13 => TriggerEvent::OnLayoutEnter,
+            14 => TriggerEvent::OnLayoutExit,
+            15 => TriggerEvent::OnLayoutSizeChange,
+            16 => TriggerEvent::OnModeEnter,
+            17 => TriggerEvent::OnModeExit,
+            18 => TriggerEvent::OnViewChange,
+            19 => TriggerEvent::OnGestureTap,
+            20 => TriggerEvent::OnFirstWindowOpen,
+            21 => TriggerEvent::OnLastWindowClose,
+            22 => TriggerEvent::OnWindowOpen,
+            23 => TriggerEvent::OnWindowClose,
+            24 => TriggerEvent::OnFileAVPlayerChange,
+            25 => TriggerEvent::OnObjectAVPlayerChange,
+            26 => TriggerEvent::OnWindowTransaction,
+            27 => TriggerEvent::OnExternalCommandReceived,
+            n => TriggerEvent::Unknown(n),
+        }
+    }
+}
+
+/// What a trigger is attached to.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TriggerSource {
+    #[default]
+    File,
+    Layout { layout: usize },
+    /// A layout object, by layout id and the object ids from the outermost object down.
+    LayoutObject { layout: usize, object: Vec<usize> },
+}
+
+/// A script run when `event` happens on `source`.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Trigger {
+    pub event: TriggerEvent,
+    pub source: TriggerSource,
+    pub script_id: usize,
+    pub script_name: String,
+    pub parameter_calc: Option<Result<Calc, CalcError>>,
+}
+
+/* Trigger definitions at key 2: the event, then the script id. Button actions leave the event byte unused. */
+pub fn decode_trigger(data: &[u8], source: TriggerSource) -> Trigger {
+    Trigger {
+        event: TriggerEvent::from(data.first().copied().unwrap_or(0)),
+        source,
+        script_id: data.get(1..3).map_or(0, get_int),
+        script_name: String::new(),
+        parameter_calc: None,
+    }
+}
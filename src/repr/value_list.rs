@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+use crate::repr::component::FMComponentValueList;
+use crate::util::format_decode::get_int;
+
+/// Where a value list takes its values from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ValueListSource {
+    #[default]
+    CustomValues,
+    Field,
+    /// A value list defined in another file, through one of this file's data sources,
+    /// by its id in that file.
+    ExternalFile { data_source: u16, value_list: u16 },
+    Unknown(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ValueListSort {
+    #[default]
+    FirstField,
+    SecondField,
+}
+
+/// A field values are taken from, by table occurrence and field id.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValueListField {
+    pub table_occurrence: u16,
+    pub field: u16,
+    pub field_name: String,
+}
+
+/// "Include only related values starting from" a table occurrence.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelatedFilter {
+    pub table_occurrence: u16,
+    pub table_occurrence_name: String,
+}
+
+/* Switches at key 2: byte 0 source, byte 1 flags, byte 2 sort order. */
+pub fn decode_value_list_switches(list: &mut FMComponentValueList, switches: &[u8]) {
+    let byte = |i: usize| switches.get(i).copied().unwrap_or(0);
+    list.source = match byte(0) {
+        0 => ValueListSource::CustomValues,
+        1 => ValueListSource::Field,
+        2 => ValueListSource::ExternalFile { data_source: 0, value_list: 0 },
+        n => ValueListSource::Unknown(n),
+    };
+    list.related_only = (byte(1) & 0x01 != 0).then(RelatedFilter::default);
+    list.sort = if byte(2) == 1 { ValueListSort::SecondField } else { ValueListSort::FirstField };
+}
+
+/* Fields are stored as a table occurrence id and a field id, both 2 bytes. */
+pub fn decode_value_list_field(data: &[u8]) -> ValueListField {
+    ValueListField {
+        table_occurrence: data.get(0..2).map_or(0, get_int) as u16,
+        field: data.get(2..4).map_or(0, get_int) as u16,
+        field_name: String::new(),
+    }
+}
+
+/* Custom values are a single string with one value per line. */
+pub fn decode_custom_values(text: &str) -> Vec<String> {
+    text.split(['\r', '\n'])
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::repr::component::FMComponentValueList;
+    use crate::repr::value_list::*;
+
+    #[test]
+    fn value_list_decoding() {
+        let mut list = FMComponentValueList::new();
+        decode_value_list_switches(&mut list, &[1, 1, 1]);
+        assert_eq!(list.source, ValueListSource::Field);
+        assert_eq!(list.related_only, Some(RelatedFilter::default()));
+        assert_eq!(list.sort, ValueListSort::SecondField);
+
+        decode_value_list_switches(&mut list, &[0]);
+        assert_eq!(list.source, ValueListSource::CustomValues);
+        assert_eq!(list.related_only, None);
+
+        assert_eq!(decode_custom_values("Yes\rNo\r\rMaybe"), vec!["Yes", "No", "Maybe"]);
+        let field = decode_value_list_field(&[0, 3, 0, 9]);
+        assert_eq!((field.table_occurrence, field.field), (3, 9));
+    }
+}
@@ -1,5 +1,6 @@
 use std::str::FromStr;
 use serde::{Deserialize, Serialize};
+use crate::script_engine::step_params::StepParams;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[repr(u8)] pub enum Instruction {
@@ -197,6 +198,206 @@ use serde::{Deserialize, Serialize};
     Assert = 255,
 }
 
+impl Instruction {
+    /// The step name as FileMaker displays it in the Script Workspace.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Instruction::PerformScript => "Perform Script",
+            Instruction::SaveACopyAsXml => "Save a Copy as XML",
+            Instruction::GoToNextField => "Go to Next Field",
+            Instruction::GoToPreviousField => "Go to Previous Field",
+            Instruction::GoToLayout => "Go to Layout",
+            Instruction::NewRecordRequest => "New Record/Request",
+            Instruction::DuplicateRecordRequest => "Duplicate Record/Request",
+            Instruction::DeleteRecordRequest => "Delete Record/Request",
+            Instruction::DeleteAllRecords => "Delete All Records",
+            Instruction::InsertFromIndex => "Insert from Index",
+            Instruction::InsertFromLastVisited => "Insert from Last Visited",
+            Instruction::InsertCurrentDate => "Insert Current Date",
+            Instruction::InsertCurrentTime => "Insert Current Time",
+            Instruction::GoToRecordRequestPage => "Go to Record/Request/Page",
+            Instruction::GoToField => "Go to Field",
+            Instruction::CheckSelection => "Check Selection",
+            Instruction::CheckRecord => "Check Record",
+            Instruction::CheckFoundSet => "Check Found Set",
+            Instruction::UnsortRecords => "Unsort Records",
+            Instruction::EnterFindMode => "Enter Find Mode",
+            Instruction::ShowAllRecords => "Show All Records",
+            Instruction::ModifyLastFind => "Modify Last Find",
+            Instruction::OmitRecord => "Omit Record",
+            Instruction::OmitMultipleRecords => "Omit Multiple Records",
+            Instruction::ShowOmmitedOnly => "Show Omitted Only",
+            Instruction::PerformFind => "Perform Find",
+            Instruction::ShowHideToolbars => "Show/Hide Toolbars",
+            Instruction::ViewAs => "View As",
+            Instruction::AdjustWindow => "Adjust Window",
+            Instruction::OpenHelp => "Open Help",
+            Instruction::OpenFile => "Open File",
+            Instruction::CloseFile => "Close File",
+            Instruction::ImportRecords => "Import Records",
+            Instruction::ExportRecords => "Export Records",
+            Instruction::SaveACopyAs => "Save a Copy as",
+            Instruction::OpenManageDatabase => "Open Manage Database",
+            Instruction::SortRecords => "Sort Records",
+            Instruction::RelookupFieldContents => "Relookup Field Contents",
+            Instruction::EnterPreviewMode => "Enter Preview Mode",
+            Instruction::PrintSetup => "Print Setup",
+            Instruction::Print => "Print",
+            Instruction::ExitApplication => "Exit Application",
+            Instruction::UndoRedo => "Undo/Redo",
+            Instruction::Cut => "Cut",
+            Instruction::Copy => "Copy",
+            Instruction::Paste => "Paste",
+            Instruction::Clear => "Clear",
+            Instruction::SelectAll => "Select All",
+            Instruction::RevertRecordRequest => "Revert Record/Request",
+            Instruction::EnterBrowserMode => "Enter Browse Mode",
+            Instruction::InsertPicture => "Insert Picture",
+            Instruction::SendEvent => "Send Event",
+            Instruction::InsertCurrentUserName => "Insert Current User Name",
+            Instruction::InsertText => "Insert Text",
+            Instruction::PauseResumeScript => "Pause/Resume Script",
+            Instruction::SendMail => "Send Mail",
+            Instruction::SendDdeExecute => "Send DDE Execute",
+            Instruction::DialPhone => "Dial Phone",
+            Instruction::Speak => "Speak",
+            Instruction::PerformApplescript => "Perform AppleScript",
+            Instruction::If => "If",
+            Instruction::Else => "Else",
+            Instruction::EndIf => "End If",
+            Instruction::Loop => "Loop",
+            Instruction::ExitLoopIf => "Exit Loop If",
+            Instruction::EndLoop => "End Loop",
+            Instruction::GoToRelatedRecord => "Go to Related Record",
+            Instruction::CommitRecordsRequests => "Commit Records/Requests",
+            Instruction::SetField => "Set Field",
+            Instruction::InsertCalculatedResult => "Insert Calculated Result",
+            Instruction::FreezeWindow => "Freeze Window",
+            Instruction::RefreshWindow => "Refresh Window",
+            Instruction::ScrollWindow => "Scroll Window",
+            Instruction::NewFile => "New File",
+            Instruction::ChangePassword => "Change Password",
+            Instruction::SetMultiUser => "Set Multi-User",
+            Instruction::AllowUserAbort => "Allow User Abort",
+            Instruction::SetErrorCapture => "Set Error Capture",
+            Instruction::ShowCustomDialog => "Show Custom Dialog",
+            Instruction::OpenScriptWorkspace => "Open Script Workspace",
+            Instruction::BlankLineComment => "#",
+            Instruction::HaltScript => "Halt Script",
+            Instruction::ReplaceFieldContents => "Replace Field Contents",
+            Instruction::ShowHideTextRuler => "Show/Hide Text Ruler",
+            Instruction::Beep => "Beep",
+            Instruction::SetUseSystemFormats => "Set Use System Formats",
+            Instruction::RecoverFile => "Recover File",
+            Instruction::SaveACopyAsAddOnPackage => "Save a Copy as Add-on Package",
+            Instruction::SetZoomLevel => "Set Zoom Level",
+            Instruction::CopyAllRecordsRequests => "Copy All Records/Requests",
+            Instruction::GoToPortalRow => "Go to Portal Row",
+            Instruction::CopyRecordRequest => "Copy Record/Request",
+            Instruction::FluchCacheToDisk => "Flush Cache to Disk",
+            Instruction::ExitScript => "Exit Script",
+            Instruction::DeletePortalRow => "Delete Portal Row",
+            Instruction::OpenPreferences => "Open Preferences",
+            Instruction::CorrectWord => "Correct Word",
+            Instruction::SpellingOptions => "Spelling Options",
+            Instruction::SelectDictionaries => "Select Dictionaries",
+            Instruction::EditUserDictionary => "Edit User Dictionary",
+            Instruction::OpenUrl => "Open URL",
+            Instruction::OpenManageValueLists => "Open Manage Value Lists",
+            Instruction::OpenSharing => "Open Sharing",
+            Instruction::OpenFileOptions => "Open File Options",
+            Instruction::AllowFormattingBar => "Allow Formatting Bar",
+            Instruction::SetNextSerialValue => "Set Next Serial Value",
+            Instruction::ExecuteSql => "Execute SQL",
+            Instruction::OpenHosts => "Open Hosts",
+            Instruction::MoveResizeWindow => "Move/Resize Window",
+            Instruction::ArrangeAllWindows => "Arrange All Windows",
+            Instruction::CloseWindow => "Close Window",
+            Instruction::NewWindow => "New Window",
+            Instruction::SelectWindow => "Select Window",
+            Instruction::SetWindowTitle => "Set Window Title",
+            Instruction::ElseIf => "Else If",
+            Instruction::ConstrainFoundSet => "Constrain Found Set",
+            Instruction::ExtendFoundSet => "Extend Found Set",
+            Instruction::PerformFindReplace => "Perform Find/Replace",
+            Instruction::OpenFindReplace => "Open Find/Replace",
+            Instruction::SetSelection => "Set Selection",
+            Instruction::InsertFile => "Insert File",
+            Instruction::ExportFieldContents => "Export Field Contents",
+            Instruction::OpenRecordRequest => "Open Record/Request",
+            Instruction::AddAccount => "Add Account",
+            Instruction::DeleteAccount => "Delete Account",
+            Instruction::ResetAccountPassword => "Reset Account Password",
+            Instruction::EnableAccount => "Enable Account",
+            Instruction::Relogin => "Re-Login",
+            Instruction::ConvertFile => "Convert File",
+            Instruction::OpenManageDataSources => "Open Manage Data Sources",
+            Instruction::SetVariable => "Set Variable",
+            Instruction::InstallMenuSet => "Install Menu Set",
+            Instruction::SaveRecordsAsExcel => "Save Records as Excel",
+            Instruction::SaveRecordsAsPdf => "Save Records as PDF",
+            Instruction::GoToObject => "Go to Object",
+            Instruction::SetWebViewer => "Set Web Viewer",
+            Instruction::SetFieldByName => "Set Field By Name",
+            Instruction::InstallOntimerScript => "Install OnTimer Script",
+            Instruction::OpenEditSavedFinds => "Open Edit Saved Finds",
+            Instruction::PerformQuickFind => "Perform Quick Find",
+            Instruction::OpenManageLayouts => "Open Manage Layouts",
+            Instruction::SaveRecordsAsSnapshotLink => "Save Records as Snapshot Link",
+            Instruction::SortRecordsByField => "Sort Records by Field",
+            Instruction::FindMatchingRecords => "Find Matching Records",
+            Instruction::ManageContainers => "Manage Containers",
+            Instruction::InstallPluginFile => "Install Plug-In File",
+            Instruction::InsertPdf => "Insert PDF",
+            Instruction::InsertAudioVideo => "Insert Audio/Video",
+            Instruction::InsertFromUrl => "Insert from URL",
+            Instruction::InsertFromDevice => "Insert from Device",
+            Instruction::PerformScriptOnServer => "Perform Script on Server",
+            Instruction::OpenManageThemes => "Open Manage Themes",
+            Instruction::ShowHideMenubar => "Show/Hide Menubar",
+            Instruction::RefreshObject => "Refresh Object",
+            Instruction::SetLayoutObjectAnimation => "Set Layout Object Animation",
+            Instruction::ClosePopover => "Close Popover",
+            Instruction::OpenUploadToHost => "Open Upload to Host",
+            Instruction::EnableTouchKeyboard => "Enable Touch Keyboard",
+            Instruction::PerformJavascriptInWebViewer => "Perform JavaScript in Web Viewer",
+            Instruction::CommentedOut => "Commented Out",
+            Instruction::AvplayerPlay => "AVPlayer Play",
+            Instruction::AvplayerSetPlaybackState => "AVPlayer Set Playback State",
+            Instruction::AvplayerSetOptions => "AVPlayer Set Options",
+            Instruction::RefreshPortal => "Refresh Portal",
+            Instruction::GetFolderPath => "Get Folder Path",
+            Instruction::TruncateTable => "Truncate Table",
+            Instruction::OpenFavorites => "Open Favorites",
+            Instruction::ConfigureRegionMonitorScript => "Configure Region Monitor Script",
+            Instruction::ConfigureLocalNotification => "Configure Local Notification",
+            Instruction::GetFileExists => "Get File Exists",
+            Instruction::GetFileSize => "Get File Size",
+            Instruction::CreateDataFile => "Create Data File",
+            Instruction::OpenDataFile => "Open Data File",
+            Instruction::WriteToDataFile => "Write to Data File",
+            Instruction::ReadFromDataFile => "Read from Data File",
+            Instruction::GetDataFilePosition => "Get Data File Position",
+            Instruction::SetDataFilePosition => "Set Data File Position",
+            Instruction::CloseDataFile => "Close Data File",
+            Instruction::DeleteFile => "Delete File",
+            Instruction::RenameFile => "Rename File",
+            Instruction::SetErrorLogging => "Set Error Logging",
+            Instruction::ConfigureNfcReading => "Configure NFC Reading",
+            Instruction::ConfigureMachineLearningModel => "Configure Machine Learning Model",
+            Instruction::ExecuteFilemakerDataApi => "Execute FileMaker Data API",
+            Instruction::OpenTransaction => "Open Transaction",
+            Instruction::CommitTransaction => "Commit Transaction",
+            Instruction::RevertTransaction => "Revert Transaction",
+            Instruction::SetSessionIdentifier => "Set Session Identifier",
+            Instruction::SetDictionary => "Set Dictionary",
+            Instruction::PerformScriptOnServerWithCallback => "Perform Script on Server with Callback",
+            Instruction::TriggerClarisConnectFlow => "Trigger Claris Connect Flow",
+            Instruction::Assert => "Assert",
+        }
+    }
+}
+
 impl FromStr for Instruction {
     type Err = ();
     fn from_str(input: &str) -> Result<Instruction, Self::Err> {
@@ -479,7 +680,18 @@ pub static INSTRUCTIONMAP : [Option<Instruction>; 255] = [
 pub struct ScriptStep {
     pub opcode: Instruction,
     pub index: usize,
-    pub switches: Vec<String>,
+    /// The step's options, decoded for its instruction.
+    #[serde(default)]
+    pub params: StepParams,
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+impl ScriptStep {
+    /// The options as shown between the brackets of the Script Workspace.
+    pub fn switches(&self) -> Vec<String> {
+        self.params.switches()
+    }
 }
 
 pub struct Script {
@@ -487,3 +699,13 @@ pub struct Script {
     pub instructions: Vec<Instruction>,
 }
 
+#[cfg(test)]
+mod tests {
+    use crate::script_engine::instructions::*;
+
+    #[test]
+    fn step_defaults() {
+        let step: ScriptStep = serde_json::from_str(r#"{"opcode":"Beep","index":3}"#).unwrap();
+        assert_eq!(step, ScriptStep { opcode: Instruction::Beep, index: 3, params: StepParams::None, disabled: false });
+    }
+}
@@ -1 +1,3 @@
 pub mod instructions;
+pub mod script_text;
+pub mod step_params;
@@ -0,0 +1,110 @@
+use crate::repr::component::FMComponentScript;
+use crate::script_engine::instructions::{Instruction, ScriptStep};
+
+const INDENT : &str = "    ";
+
+/// Render a single step the way the Script Workspace shows it, without indentation.
+pub fn step_to_text(step: &ScriptStep) -> String {
+    let name = step.opcode.name();
+    let switches = step.switches();
+    let text = match (&step.opcode, switches.as_slice()) {
+        (Instruction::BlankLineComment, []) => String::new(),
+        (Instruction::BlankLineComment, [comment, ..]) => format!("# {}", comment),
+        (Instruction::SetVariable, [var]) => format!("{} [ {} ]", name, var),
+        (Instruction::SetVariable, [var, .., value]) => format!("{} [ {} ; Value: {} ]", name, var, value),
+        (Instruction::ExitScript, [result]) => format!("{} [ Text Result: {} ]", name, result),
+        (_, []) => name.to_string(),
+        (_, switches) => format!("{} [ {} ]", name, switches.join(" ; ")),
+    };
+
+    if step.disabled {
+        format!("// {}", text)
+    } else {
+        text
+    }
+}
+
+/// Render a whole script as text, indenting the bodies of `If` and `Loop` blocks.
+pub fn script_to_text(script: &FMComponentScript) -> String {
+    let mut steps = script.instructions.iter().collect::<Vec<_>>();
+    steps.sort_by_key(|(position, _)| **position);
+
+    let mut depth = 0usize;
+    let mut result = String::new();
+    for (_, step) in steps {
+        if matches!(step.opcode, Instruction::Else | Instruction::ElseIf | Instruction::EndIf | Instruction::EndLoop) {
+            depth = depth.saturating_sub(1);
+        }
+
+        let text = step_to_text(step);
+        if !text.is_empty() {
+            result.push_str(&INDENT.repeat(depth));
+            result.push_str(&text);
+        }
+        result.push('\n');
+
+        if matches!(step.opcode, Instruction::If | Instruction::Else | Instruction::ElseIf | Instruction::Loop) {
+            depth += 1;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::repr::component::FMComponentScript;
+    use crate::script_engine::instructions::{Instruction, ScriptStep};
+    use crate::script_engine::script_text::*;
+    use crate::script_engine::step_params::StepParams;
+
+    fn step(opcode: Instruction, switches: &[&str]) -> ScriptStep {
+        ScriptStep {
+            opcode,
+            index: 0,
+            params: StepParams::Other {
+                text: switches.iter().map(|s| s.to_string()).collect(),
+                fields: vec![],
+                calculations: vec![],
+                ids: vec![],
+            },
+            disabled: false,
+        }
+    }
+
+    #[test]
+    fn script_text_testing() {
+        let mut script = FMComponentScript::new();
+        let mut disabled = step(Instruction::Beep, &[]);
+        disabled.disabled = true;
+        let steps = [
+            step(Instruction::BlankLineComment, &["Count up"]),
+            step(Instruction::SetVariable, &["$x", "1 + 2"]),
+            step(Instruction::Loop, &[]),
+            step(Instruction::ExitLoopIf, &["$x > 10"]),
+            step(Instruction::If, &["$x = 5"]),
+            disabled,
+            step(Instruction::Else, &[]),
+            step(Instruction::BlankLineComment, &[]),
+            step(Instruction::EndIf, &[]),
+            step(Instruction::EndLoop, &[]),
+            step(Instruction::ExitScript, &["$x"]),
+        ];
+        for (i, s) in steps.into_iter().enumerate() {
+            script.instructions.insert(i, s);
+        }
+
+        assert_eq!(script_to_text(&script), "\
+# Count up
+Set Variable [ $x ; Value: 1 + 2 ]
+Loop
+    Exit Loop If [ $x > 10 ]
+    If [ $x = 5 ]
+        // Beep
+    Else
+
+    End If
+End Loop
+Exit Script [ Text Result: $x ]
+");
+    }
+}
@@ -0,0 +1,197 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use crate::calc_engine::calc::{Calc, FieldRef};
+use crate::calc_engine::decode::CalcError;
+
+/// A calculation option of a step, or why it could not be decoded.
+pub type StepCalc = Result<Calc, CalcError>;
+
+/* Calculations that fail to decode are kept visible in the output rather than dropped. */
+fn render(calc: &StepCalc) -> String {
+    match calc {
+        Ok(calc) => calc.to_string(),
+        Err(e) => format!("<{}>", e),
+    }
+}
+
+fn render_option(calc: &Option<StepCalc>) -> String {
+    calc.as_ref().map(render).unwrap_or_default()
+}
+
+/// Layout a `Go to Layout` step switches to.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub enum LayoutTarget {
+    #[default]
+    Original,
+    Layout { id: usize, name: String },
+    ByNameCalc(Option<StepCalc>),
+    ByNumberCalc(Option<StepCalc>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Animation {
+    #[default]
+    None,
+    SlideFromLeft,
+    SlideFromRight,
+    SlideFromBottom,
+    SlideToLeft,
+    SlideToRight,
+    SlideToBottom,
+    FlipFromLeft,
+    FlipFromRight,
+    ZoomIn,
+    ZoomOut,
+    CrossDissolve,
+    Unknown(u8),
+}
+
+impl From<u8> for Animation {
+    fn from(byte: u8) -> Self {
+        match byte {
+            0 => Animation::None,
+            1 => Animation::SlideFromLeft,
+            2 => Animation::SlideFromRight,
+            3 => Animation::SlideFromBottom,
+            4 => Animation::SlideToLeft,
+            5 => Animation::SlideToRight,
+            6 => Animation::SlideToBottom,
+            7 => Animation::FlipFromLeft,
+            8 => Animation::FlipFromRight,
+            9 => Animation::ZoomIn,
+            10 => Animation::ZoomOut,
+            11 => Animation::CrossDissolve,
+            n => Animation::Unknown(n),
+        }
+    }
+}
+
+/* The wording of the animation menu in the step's options dialog. */
+impl fmt::Display for Animation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Animation::None => write!(f, "None"),
+            Animation::SlideFromLeft => write!(f, "Slide in from Left"),
+            Animation::SlideFromRight => write!(f, "Slide in from Right"),
+            Animation::SlideFromBottom => write!(f, "Slide in from Bottom"),
+            Animation::SlideToLeft => write!(f, "Slide out to Left"),
+            Animation::SlideToRight => write!(f, "Slide out to Right"),
+            Animation::SlideToBottom => write!(f, "Slide out to Bottom"),
+            Animation::FlipFromLeft => write!(f, "Flip from Left"),
+            Animation::FlipFromRight => write!(f, "Flip from Right"),
+            Animation::ZoomIn => write!(f, "Zoom In"),
+            Animation::ZoomOut => write!(f, "Zoom Out"),
+            Animation::CrossDissolve => write!(f, "Cross Dissolve"),
+            Animation::Unknown(n) => write!(f, "<animation {}>", n),
+        }
+    }
+}
+
+/// Decoded options of a script step. Steps without a dedicated variant keep
+/// their options as text, fields and calculations in order of appearance.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub enum StepParams {
+    #[default]
+    None,
+    Comment { text: String },
+    /// Steps whose only option is a calculation, such as `If` or `Exit Script`.
+    Calculation { calc: Option<StepCalc> },
+    SetVariable { name: String, value: Option<StepCalc>, repetition: Option<StepCalc> },
+    SetField { target: Option<FieldRef>, calc: Option<StepCalc>, repetition: Option<StepCalc> },
+    GoToLayout { target: LayoutTarget, animation: Animation },
+    PerformScript { script: usize, script_name: String, from_file: Option<usize>, parameter: Option<StepCalc> },
+    ShowCustomDialog { title: Option<StepCalc>, message: Option<StepCalc>, buttons: Vec<String>, inputs: Vec<FieldRef> },
+    /// `ids` holds the key 2 data of steps not known to take a field, whose meaning is unknown.
+    Other { text: Vec<String>, fields: Vec<FieldRef>, calculations: Vec<StepCalc>, ids: Vec<Vec<u8>> },
+}
+
+impl StepParams {
+    /// Every calculation option that could be decoded, to name the fields it refers to in place.
+    pub fn calcs_mut(&mut self) -> Vec<&mut Calc> {
+        let calcs: Vec<&mut StepCalc> = match self {
+            StepParams::None | StepParams::Comment { .. } => vec![],
+            StepParams::GoToLayout { target: LayoutTarget::Original | LayoutTarget::Layout { .. }, .. } => vec![],
+            StepParams::Calculation { calc } => calc.iter_mut().collect(),
+            StepParams::SetVariable { value, repetition, .. } => value.iter_mut().chain(repetition).collect(),
+            StepParams::SetField { calc, repetition, .. } => calc.iter_mut().chain(repetition).collect(),
+            StepParams::GoToLayout { target: LayoutTarget::ByNameCalc(calc) | LayoutTarget::ByNumberCalc(calc), .. } => {
+                calc.iter_mut().collect()
+            },
+            StepParams::PerformScript { parameter, .. } => parameter.iter_mut().collect(),
+            StepParams::ShowCustomDialog { title, message, .. } => title.iter_mut().chain(message).collect(),
+            StepParams::Other { calculations, .. } => calculations.iter_mut().collect(),
+        };
+        calcs.into_iter().filter_map(|calc| calc.as_mut().ok()).collect()
+    }
+
+    /// Every field option, such as the target of `Set Field`, to name them in place.
+    pub fn fields_mut(&mut self) -> Vec<&mut FieldRef> {
+        match self {
+            StepParams::SetField { target, .. } => target.iter_mut().collect(),
+            StepParams::ShowCustomDialog { inputs, .. } => inputs.iter_mut().collect(),
+            StepParams::Other { fields, .. } => fields.iter_mut().collect(),
+            _ => vec![],
+        }
+    }
+
+    /// The options as shown between the brackets of the Script Workspace.
+    pub fn switches(&self) -> Vec<String> {
+        let switches = match self {
+            StepParams::None => vec![],
+            StepParams::Comment { text } => vec![text.clone()],
+            StepParams::Calculation { calc } => vec![render_option(calc)],
+            StepParams::SetVariable { name, value, repetition } => {
+                let name = match repetition {
+                    Some(r) => format!("{}[{}]", name, render(r)),
+                    None => name.clone(),
+                };
+                vec![name, render_option(value)]
+            },
+            StepParams::SetField { target, calc, repetition } => {
+                let target = target.as_ref().map(|t| t.to_string()).unwrap_or_default();
+                let target = match repetition {
+                    Some(r) => format!("{}[{}]", target, render(r)),
+                    None => target,
+                };
+                vec![target, render_option(calc)]
+            },
+            StepParams::GoToLayout { target, animation } => {
+                let mut switches = vec![match target {
+                    LayoutTarget::Original => "original layout".to_string(),
+                    LayoutTarget::Layout { id, name } if name.is_empty() => format!("<layout {}>", id),
+                    LayoutTarget::Layout { name, .. } => format!("\"{}\"", name),
+                    LayoutTarget::ByNameCalc(calc) => format!("Layout Name: {}", render_option(calc)),
+                    LayoutTarget::ByNumberCalc(calc) => format!("Layout Number: {}", render_option(calc)),
+                }];
+                if *animation != Animation::None {
+                    switches.push(format!("Animation: {}", animation));
+                }
+                switches
+            },
+            StepParams::PerformScript { script, script_name, parameter, .. } => {
+                let mut switches = vec![match script_name.as_str() {
+                    "" => format!("<script {}>", script),
+                    name => format!("\"{}\"", name),
+                }];
+                switches.extend(parameter.iter().map(|p| format!("Parameter: {}", render(p))));
+                switches
+            },
+            StepParams::ShowCustomDialog { title, message, buttons, inputs } => {
+                let mut switches = vec![render_option(title), render_option(message)];
+                if !buttons.is_empty() {
+                    switches.push(format!("Buttons: {}", buttons.join(", ")));
+                }
+                switches.extend(inputs.iter().map(|input| format!("Input: {}", input)));
+                switches
+            },
+            StepParams::Other { text, fields, calculations, .. } => {
+                text.iter().cloned()
+                    .chain(fields.iter().map(|f| f.to_string()))
+                    .chain(calculations.iter().map(render))
+                    .collect()
+            },
+        };
+        switches.into_iter().filter(|s| !s.is_empty()).collect()
+    }
+}
@@ -1 +1,3 @@
 pub mod format_decode;
+#[cfg(test)]
+pub mod test_util;
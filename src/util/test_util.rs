@@ -0,0 +1,26 @@
+use crate::fmp_format::header::tests::test_header;
+use crate::fmp_format::sector::SECTOR_SIZE;
+
+/// XOR encode `s` the way FileMaker stores text.
+pub fn encrypt(s: &str) -> Vec<u8> {
+    s.bytes().map(|b| b ^ 0x5A).collect()
+}
+
+/// A file whose data sectors, chained from sector 2 on, hold the chunks in `payloads`.
+pub fn sector_chain_file(payloads: &[&[u8]]) -> Vec<u8> {
+    let mut bytes = test_header("Pro 19.0", "Test.fmp12");
+    bytes.resize(SECTOR_SIZE * (payloads.len() + 2), 0);
+    for (i, payload) in payloads.iter().enumerate() {
+        let start = SECTOR_SIZE * (i + 2);
+        if i + 1 < payloads.len() {
+            bytes[start + 8..start + 12].copy_from_slice(&(i as u32 + 3).to_be_bytes());
+        }
+        bytes[start + 20..start + 20 + payload.len()].copy_from_slice(payload);
+    }
+    bytes
+}
+
+/// A file whose only data sector, sector 2, holds the chunks in `payload`.
+pub fn single_sector_file(payload: &[u8]) -> Vec<u8> {
+    sector_chain_file(&[payload])
+}